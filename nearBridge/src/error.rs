@@ -0,0 +1,73 @@
+use std::fmt;
+
+/// Errors of the Ethereum event, header and address parsing, of the Eth→NEAR finalisation and
+/// of the rate limits.
+/// The strings returned by `as_str` are stable and used as panic messages, so relayers can
+/// classify failed transactions.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum BridgeError {
+    /// Log entry is not a valid RLP encoded Ethereum log.
+    InvalidRlp,
+    /// First topic of the log doesn't match the signature of the expected event.
+    WrongTopic,
+    /// Topics or data of the log can't be decoded as the expected event params.
+    InvalidEventData,
+    /// Decoded event param has an unexpected type.
+    WrongParamType,
+    /// Transferred amount doesn't fit into `Balance`.
+    AmountOverflow,
+    /// Transferred amount is zero.
+    ZeroAmount,
+    /// Recipient is not a valid NEAR account, optionally followed by a message.
+    InvalidRecipient,
+    /// Event wasn't emitted by the eNear contract associated with the bridge.
+    WrongEventAddress,
+    /// Ethereum address is not 20 hex encoded bytes, optionally prefixed with `0x`.
+    InvalidEthAddress,
+    /// Mixed case Ethereum address doesn't match its EIP-55 checksum.
+    InvalidEthAddressChecksum,
+    /// Ethereum address is the zero address.
+    ZeroEthAddress,
+    /// Transferred amount exceeds the maximum amount of a single transfer.
+    TransferLimitExceeded,
+    /// Transfer would exceed the maximum volume of the rolling 24 hours.
+    DailyVolumeLimitExceeded,
+    /// Recipient asks to forward the transfer to an omni bridge which isn't whitelisted.
+    OmniBridgeNotWhitelisted,
+    /// Block header of the proof is not a valid RLP encoded Ethereum header.
+    InvalidBlockHeader,
+    /// Event of the proof was already finalised.
+    ProofAlreadyUsed,
+    /// Event of the proof is being finalised by another transaction.
+    ProofBeingFinalised,
+}
+
+impl BridgeError {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BridgeError::InvalidRlp => "ERR_INVALID_RLP",
+            BridgeError::WrongTopic => "ERR_WRONG_TOPIC",
+            BridgeError::InvalidEventData => "ERR_INVALID_EVENT_DATA",
+            BridgeError::WrongParamType => "ERR_WRONG_PARAM_TYPE",
+            BridgeError::AmountOverflow => "ERR_AMOUNT_OVERFLOW",
+            BridgeError::ZeroAmount => "ERR_ZERO_AMOUNT",
+            BridgeError::InvalidRecipient => "ERR_INVALID_RECIPIENT",
+            BridgeError::WrongEventAddress => "ERR_WRONG_EVENT_ADDRESS",
+            BridgeError::InvalidEthAddress => "ERR_INVALID_ETH_ADDRESS",
+            BridgeError::InvalidEthAddressChecksum => "ERR_INVALID_ETH_ADDRESS_CHECKSUM",
+            BridgeError::ZeroEthAddress => "ERR_ZERO_ETH_ADDRESS",
+            BridgeError::TransferLimitExceeded => "ERR_TRANSFER_LIMIT_EXCEEDED",
+            BridgeError::DailyVolumeLimitExceeded => "ERR_DAILY_VOLUME_LIMIT_EXCEEDED",
+            BridgeError::OmniBridgeNotWhitelisted => "ERR_OMNI_BRIDGE_NOT_WHITELISTED",
+            BridgeError::InvalidBlockHeader => "ERR_INVALID_BLOCK_HEADER",
+            BridgeError::ProofAlreadyUsed => "ERR_PROOF_ALREADY_USED",
+            BridgeError::ProofBeingFinalised => "ERR_PROOF_BEING_FINALISED",
+        }
+    }
+}
+
+impl fmt::Display for BridgeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -0,0 +1,158 @@
+use std::fmt;
+use std::str::FromStr;
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::env;
+use near_sdk::serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::BridgeError;
+
+/// Ethereum address. It is serialized with borsh as raw 20 bytes and displayed in the EIP-55
+/// checksummed form with the `0x` prefix.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash, BorshSerialize, BorshDeserialize)]
+pub struct EthAddress(pub [u8; 20]);
+
+impl EthAddress {
+    pub fn is_zero(&self) -> bool {
+        self.0 == [0u8; 20]
+    }
+
+    /// Returns the EIP-55 checksummed hex representation of the address without the `0x` prefix.
+    pub fn to_checksum_hex(&self) -> String {
+        let address = hex::encode(self.0);
+        let hash = env::keccak256(address.as_bytes());
+
+        address
+            .chars()
+            .enumerate()
+            .map(|(i, c)| {
+                let nibble = (hash[i / 2] >> if i % 2 == 0 { 4 } else { 0 }) & 0x0f;
+                if nibble >= 8 {
+                    c.to_ascii_uppercase()
+                } else {
+                    c
+                }
+            })
+            .collect()
+    }
+}
+
+impl FromStr for EthAddress {
+    type Err = BridgeError;
+
+    /// Parses 40 hex chars, optionally prefixed with `0x`. Mixed case addresses must have a valid
+    /// EIP-55 checksum. The zero address is rejected.
+    fn from_str(address: &str) -> Result<Self, Self::Err> {
+        let address = address
+            .strip_prefix("0x")
+            .or_else(|| address.strip_prefix("0X"))
+            .unwrap_or(address);
+
+        let mut result = [0u8; 20];
+        hex::decode_to_slice(address, &mut result).map_err(|_| BridgeError::InvalidEthAddress)?;
+        let result = EthAddress(result);
+
+        let has_lowercase = address.chars().any(|c| c.is_ascii_lowercase());
+        let has_uppercase = address.chars().any(|c| c.is_ascii_uppercase());
+        if has_lowercase && has_uppercase && result.to_checksum_hex() != address {
+            return Err(BridgeError::InvalidEthAddressChecksum);
+        }
+
+        if result.is_zero() {
+            return Err(BridgeError::ZeroEthAddress);
+        }
+
+        Ok(result)
+    }
+}
+
+impl fmt::Display for EthAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{}", self.to_checksum_hex())
+    }
+}
+
+impl From<[u8; 20]> for EthAddress {
+    fn from(address: [u8; 20]) -> Self {
+        Self(address)
+    }
+}
+
+impl AsRef<[u8]> for EthAddress {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Serialize for EthAddress {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for EthAddress {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let address = <String as Deserialize>::deserialize(deserializer)?;
+        address.parse().map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test vectors from EIP-55.
+    const CHECKSUMMED_ADDRESSES: [&str; 4] = [
+        "5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+        "fB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+        "dbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+        "D1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+    ];
+
+    #[test]
+    fn test_checksummed_display() {
+        for address in CHECKSUMMED_ADDRESSES {
+            let parsed: EthAddress = address.to_lowercase().parse().unwrap();
+            assert_eq!(parsed.to_string(), format!("0x{}", address));
+        }
+    }
+
+    #[test]
+    fn test_parse_eth_address() {
+        let expected: EthAddress = "5aaeb6053f3e94c9b9a09f33669435e7ef1beaed".parse().unwrap();
+
+        for address in [
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed",
+            "0x5AAEB6053F3E94C9B9A09F33669435E7EF1BEAED",
+        ] {
+            assert_eq!(address.parse::<EthAddress>(), Ok(expected));
+        }
+    }
+
+    #[test]
+    fn test_parse_invalid_eth_address() {
+        assert_eq!(
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD".parse::<EthAddress>(),
+            Err(BridgeError::InvalidEthAddressChecksum)
+        );
+        assert_eq!(
+            "0x0000000000000000000000000000000000000000".parse::<EthAddress>(),
+            Err(BridgeError::ZeroEthAddress)
+        );
+        for address in [
+            "",
+            "0x",
+            "5aaeb6053f3e94c9b9a09f33669435e7ef1bea",
+            "5aaeb6053f3e94c9b9a09f33669435e7ef1beaed00",
+            "0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaeg",
+            "0x0x5aaeb6053f3e94c9b9a09f33669435e7ef1bea",
+        ] {
+            assert_eq!(
+                address.parse::<EthAddress>(),
+                Err(BridgeError::InvalidEthAddress)
+            );
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::Serialize;
+use near_sdk::{env, serde_json, AccountId};
+
+use crate::SupplyCheckpoint;
+
+/// Name of the NEP-297 standard used for the connector events.
+pub const EVENT_STANDARD: &str = "enear-connector";
+/// Version of the connector events format.
+pub const EVENT_STANDARD_VERSION: &str = "1.0.0";
+
+const EVENT_JSON_PREFIX: &str = "EVENT_JSON:";
+
+/// Events emitted by the connector as NEP-297 `EVENT_JSON:` logs.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "event", content = "data", rename_all = "snake_case")]
+pub enum Event {
+    /// NEAR was locked to be minted as eNear on Ethereum.
+    MigrateToEthereum {
+        sender: AccountId,
+        /// Amount to be minted on Ethereum, without the fee.
+        amount: U128,
+        fee: U128,
+        recipient: String,
+        nonce: u64,
+        block_height: u64,
+    },
+    /// Unwrapped wNEAR couldn't be migrated to Ethereum and was returned to the sender.
+    MigrateToEthereumFailed {
+        sender: AccountId,
+        amount: U128,
+        reason: String,
+    },
+    /// Eth→NEAR transfer was paid out with a native NEAR transfer.
+    Finalised(TransferToNear),
+    /// Eth→NEAR transfer was paid out as wNEAR through `ft_transfer_call`.
+    FinalisedViaWnear(TransferToNear),
+    /// Eth→NEAR transfer exceeded the rate limits and waits for the DAO approval.
+    TransferQueued(TransferToNear),
+    /// Eth→NEAR transfer above the large transfer threshold can be released after
+    /// `release_timestamp` in nanoseconds.
+    TransferDelayed {
+        #[serde(flatten)]
+        transfer: TransferToNear,
+        release_timestamp: U64,
+    },
+    /// Delayed transfer was vetoed and now waits for the DAO approval.
+    DelayedTransferCancelled {
+        proof_key: String,
+        cancelled_by: AccountId,
+    },
+    /// Eth→NEAR transfer could not be finalised.
+    FinalisationFailed {
+        #[serde(flatten)]
+        transfer: TransferToNear,
+        reason: String,
+    },
+    /// Part of an Eth→NEAR transfer was returned by the receiver and can be claimed.
+    RefundCredited {
+        account_id: AccountId,
+        amount: U128,
+        proof_key: String,
+    },
+    /// Credited refunds were transferred to the account.
+    RefundClaimed { account_id: AccountId, amount: U128 },
+    /// Native NEAR payout of an Eth→NEAR transfer failed and was recorded as pending.
+    PendingTransferAdded {
+        proof_key: String,
+        recipient: AccountId,
+        amount: U128,
+    },
+    /// Pending transfer was sent again, possibly to another recipient.
+    PendingTransferRetried {
+        proof_key: String,
+        recipient: AccountId,
+        amount: U128,
+    },
+    /// Liquidity was transferred to the omni bridge as wNEAR.
+    LiquiditySwept {
+        omni_bridge: AccountId,
+        amount: U128,
+    },
+    /// eNear total supply on Ethereum was submitted by the DAO.
+    SupplyCheckpointSubmitted(SupplyCheckpoint),
+    /// Accrued fees were withdrawn by the DAO.
+    FeesWithdrawn {
+        receiver_id: AccountId,
+        amount: U128,
+    },
+}
+
+/// Eth→NEAR transfer details shared by the finalisation events.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TransferToNear {
+    /// Hex encoded `Proof::get_key` of the consumed event.
+    pub proof_key: String,
+    /// Ethereum address which initiated the transfer.
+    pub sender: String,
+    /// Amount burned on Ethereum, including the fee.
+    pub amount: U128,
+    pub fee: U128,
+    /// Recipient as it was passed to `transferToNear` on Ethereum.
+    pub recipient: String,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct EventLog<'a> {
+    standard: &'static str,
+    version: &'static str,
+    #[serde(flatten)]
+    event: &'a Event,
+}
+
+impl Event {
+    pub fn to_json_string(&self) -> String {
+        let log = EventLog {
+            standard: EVENT_STANDARD,
+            version: EVENT_STANDARD_VERSION,
+            event: self,
+        };
+        serde_json::to_string(&log).unwrap_or_else(|_| env::abort())
+    }
+
+    pub fn emit(&self) {
+        env::log_str(&format!("{}{}", EVENT_JSON_PREFIX, self.to_json_string()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_to_ethereum_event_json() {
+        let event = Event::MigrateToEthereum {
+            sender: "alice.near".parse().unwrap(),
+            amount: U128(1000),
+            fee: U128(10),
+            recipient: "0x25Ac31A08EBA29067Ba4637788d1DbFB893cEBf1".to_string(),
+            nonce: 1,
+            block_height: 42,
+        };
+
+        assert_eq!(
+            event.to_json_string(),
+            r#"{"standard":"enear-connector","version":"1.0.0","event":"migrate_to_ethereum","data":{"sender":"alice.near","amount":"1000","fee":"10","recipient":"0x25Ac31A08EBA29067Ba4637788d1DbFB893cEBf1","nonce":1,"block_height":42}}"#
+        );
+    }
+
+    #[test]
+    fn test_finalisation_events_json() {
+        let transfer = TransferToNear {
+            proof_key: "abcd".to_string(),
+            sender: "00005474e89094c44da98b954eedeac495271d0f".to_string(),
+            amount: U128(1000),
+            fee: U128(0),
+            recipient: "alice.near:msg".to_string(),
+        };
+
+        assert_eq!(
+            Event::FinalisedViaWnear(transfer.clone()).to_json_string(),
+            r#"{"standard":"enear-connector","version":"1.0.0","event":"finalised_via_wnear","data":{"proof_key":"abcd","sender":"00005474e89094c44da98b954eedeac495271d0f","amount":"1000","fee":"0","recipient":"alice.near:msg"}}"#
+        );
+        assert_eq!(
+            Event::FinalisationFailed {
+                transfer,
+                reason: "Failed to verify the proof".to_string(),
+            }
+            .to_json_string(),
+            r#"{"standard":"enear-connector","version":"1.0.0","event":"finalisation_failed","data":{"proof_key":"abcd","sender":"00005474e89094c44da98b954eedeac495271d0f","amount":"1000","fee":"0","recipient":"alice.near:msg","reason":"Failed to verify the proof"}}"#
+        );
+    }
+}
@@ -0,0 +1,134 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::Balance;
+
+use crate::Direction;
+
+/// Denominator of the proportional part of the fee.
+pub const BPS_DENOMINATOR: u16 = 10_000;
+
+/// Fee charged on the transferred amount: `fixed + amount * bps / 10000` bounded by `min` and
+/// `max`.
+#[derive(Debug, Clone, Eq, PartialEq, BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Fee {
+    /// Fixed part of the fee in yoctoNEAR.
+    pub fixed: U128,
+    /// Proportional part of the fee in basis points of the amount.
+    pub bps: u16,
+    pub min: U128,
+    /// No upper bound if not set.
+    pub max: Option<U128>,
+}
+
+// Not derived, since `U128` doesn't implement `Default` in near-sdk 4.1.
+impl Default for Fee {
+    fn default() -> Self {
+        Self {
+            fixed: U128(0),
+            bps: 0,
+            min: U128(0),
+            max: None,
+        }
+    }
+}
+
+impl Fee {
+    pub fn compute(&self, amount: Balance) -> Balance {
+        let bps = Balance::from(self.bps);
+        let denominator = Balance::from(BPS_DENOMINATOR);
+        let proportional = amount / denominator * bps + amount % denominator * bps / denominator;
+
+        let fee = self.fixed.0.saturating_add(proportional).max(self.min.0);
+        match self.max {
+            Some(max) => fee.min(max.0),
+            None => fee,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.bps > BPS_DENOMINATOR {
+            return Err("Fee basis points must not exceed 10000");
+        }
+        if matches!(self.max, Some(max) if max.0 < self.min.0) {
+            return Err("Maximum fee must not be less than the minimum fee");
+        }
+        Ok(())
+    }
+}
+
+/// Fees of both directions, set by the DAO. No fees are charged by default.
+#[derive(
+    Debug, Default, Clone, Eq, PartialEq, BorshSerialize, BorshDeserialize, Serialize, Deserialize,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FeeConfig {
+    pub to_ethereum: Fee,
+    pub to_near: Fee,
+}
+
+impl FeeConfig {
+    pub fn get(&self, direction: Direction) -> &Fee {
+        match direction {
+            Direction::ToEthereum => &self.to_ethereum,
+            Direction::ToNear => &self.to_near,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fee(fixed: Balance, bps: u16, min: Balance, max: Option<Balance>) -> Fee {
+        Fee {
+            fixed: U128(fixed),
+            bps,
+            min: U128(min),
+            max: max.map(U128),
+        }
+    }
+
+    #[test]
+    fn test_compute_fee() {
+        assert_eq!(Fee::default().compute(1_000_000), 0);
+        assert_eq!(fee(100, 0, 0, None).compute(1_000_000), 100);
+        assert_eq!(fee(0, 30, 0, None).compute(1_000_000), 3_000);
+        assert_eq!(fee(100, 30, 0, None).compute(1_000_000), 3_100);
+        assert_eq!(fee(0, 30, 0, None).compute(12_345), 37);
+        assert_eq!(fee(0, 30, 500, None).compute(10_000), 500);
+        assert_eq!(fee(100, 30, 0, Some(1_000)).compute(1_000_000), 1_000);
+        assert_eq!(fee(0, 10_000, 0, None).compute(Balance::MAX), Balance::MAX);
+        assert_eq!(
+            fee(Balance::MAX, 1, 0, None).compute(Balance::MAX),
+            Balance::MAX
+        );
+    }
+
+    #[test]
+    fn test_validate_fee() {
+        assert_eq!(fee(0, 10_000, 0, None).validate(), Ok(()));
+        assert_eq!(fee(0, 0, 100, Some(100)).validate(), Ok(()));
+        assert!(fee(0, 10_001, 0, None).validate().is_err());
+        assert!(fee(0, 0, 101, Some(100)).validate().is_err());
+    }
+
+    #[test]
+    fn test_fee_config_json() {
+        let config = FeeConfig {
+            to_ethereum: fee(100, 30, 0, None),
+            to_near: fee(0, 0, 5, Some(10)),
+        };
+
+        let json = near_sdk::serde_json::to_string(&config).unwrap();
+        assert_eq!(
+            json,
+            r#"{"to_ethereum":{"fixed":"100","bps":30,"min":"0","max":null},"to_near":{"fixed":"0","bps":0,"min":"5","max":"10"}}"#
+        );
+        assert_eq!(
+            near_sdk::serde_json::from_str::<FeeConfig>(&json).unwrap(),
+            config
+        );
+    }
+}
@@ -6,32 +6,128 @@ use near_plugins::{
 * Bridge for Near Native token
 */
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::UnorderedSet;
-use near_sdk::json_types::U128;
+use near_sdk::collections::{LookupMap, UnorderedMap, UnorderedSet, Vector};
+use near_sdk::json_types::{U128, U64};
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{
-    env, ext_contract, near_bindgen, AccountId, Balance, Gas, PanicOnDefault, Promise,
-    PromiseOrValue, PublicKey, ONE_YOCTO,
+    env, ext_contract, near_bindgen, AccountId, Balance, BlockHeight, Gas, PanicOnDefault, Promise,
+    PromiseError, PromiseOrValue, PromiseResult, PublicKey, StorageUsage, ONE_YOCTO,
 };
 use prover::ext_prover;
 pub use prover::{get_eth_address, is_valid_eth_address, EthAddress, Proof};
 pub use transfer_to_near_event::TransferToNearInitiatedEvent;
 
-use crate::prover::{parse_recipient, Recipient};
+pub use crate::error::BridgeError;
+use crate::events::{Event, TransferToNear};
+pub use crate::fee::{Fee, FeeConfig};
+use crate::prover::{parse_recipient, Delivery, Recipient};
+use crate::rate_limit::VolumeWindow;
+pub use crate::rate_limit::{RateLimit, RateLimits};
 
+mod error;
+mod eth_address;
+pub mod events;
+mod fee;
 pub mod prover;
+mod rate_limit;
 mod transfer_to_near_event;
 
 /// Gas to call finalise method.
-const FINISH_FINALISE_GAS: Gas = Gas(Gas::ONE_TERA.0 * 100);
+const FINISH_FINALISE_GAS: Gas = Gas(Gas::ONE_TERA.0 * 120);
 /// Gas to call verify_log_entry on prover.
 const VERIFY_LOG_ENTRY_GAS: Gas = Gas(Gas::ONE_TERA.0 * 50);
+/// Gas used by `finalise_eth_to_near_transfers` to check a proof and schedule its finalisation,
+/// including the fees for the scheduled calls and their arguments.
+const BATCH_START_FINALISATION_GAS: Gas = Gas(Gas::ONE_TERA.0 * 30);
+/// Gas used by finalise method besides the gas attached to the payout.
+const FINISH_FINALISE_OVERHEAD_GAS: Gas = Gas(Gas::ONE_TERA.0 * 10);
+/// Gas to call report_finalisation method.
+const REPORT_FINALISATION_GAS: Gas = Gas(Gas::ONE_TERA.0 * 10);
 const WNEAR_DEPOSIT_GAS: Gas = Gas(Gas::ONE_TERA.0 * 10);
 const WNEAR_STORAGE_DEPOSIT_GAS: Gas = Gas(Gas::ONE_TERA.0 * 5);
 const FT_TRANSFER_CALL_GAS: Gas = Gas(Gas::ONE_TERA.0 * 80);
 const FT_TRANSFER_GAS: Gas = Gas(Gas::ONE_TERA.0 * 5);
+const WNEAR_WITHDRAW_GAS: Gas = Gas(Gas::ONE_TERA.0 * 5);
+/// Gas to call resolve_wnear_transfer_call method.
+const RESOLVE_WNEAR_TRANSFER_CALL_GAS: Gas = Gas(Gas::ONE_TERA.0 * 15);
+/// Gas to call resolve_native_transfer method.
+const RESOLVE_NATIVE_TRANSFER_GAS: Gas = Gas(Gas::ONE_TERA.0 * 10);
+/// Gas to call storage_balance_of on wNEAR.
+const STORAGE_BALANCE_OF_GAS: Gas = Gas(Gas::ONE_TERA.0 * 5);
+/// Gas to call send_wnear_transfer method.
+const SEND_WNEAR_TRANSFER_GAS: Gas = Gas(Gas::ONE_TERA.0 * 40);
+/// Gas to call resolve_wnear_transfer method.
+const RESOLVE_WNEAR_TRANSFER_GAS: Gas = Gas(Gas::ONE_TERA.0 * 10);
+/// Gas to call resolve_aurora_deposit method.
+const RESOLVE_AURORA_DEPOSIT_GAS: Gas = Gas(Gas::ONE_TERA.0 * 15);
+/// Gas to call resolve_omni_bridge_sweep method.
+const RESOLVE_OMNI_BRIDGE_SWEEP_GAS: Gas = Gas(Gas::ONE_TERA.0 * 10);
+/// Gas to call finish_wnear_migration method.
+const FINISH_WNEAR_MIGRATION_GAS: Gas = Gas(Gas::ONE_TERA.0 * 20);
+
+/// Storage deposit registering an account on the wNEAR contract.
+const WNEAR_STORAGE_DEPOSIT: Balance = 1_250_000_000_000_000_000_000;
 
 const WNEAR_STORAGE_KEY: &[u8] = b"wnear";
+const OUTGOING_NONCE_STORAGE_KEY: &[u8] = b"nonce";
+const OUTGOING_TRANSFERS_PREFIX: &[u8] = b"o";
+const RESULT_WITH_NONCE_STORAGE_KEY: &[u8] = b"result_with_nonce";
+const REFUNDS_STORAGE_KEY: &[u8] = b"refunds";
+const PENDING_TRANSFERS_STORAGE_KEY: &[u8] = b"pending";
+const PENDING_TRANSFERS_PREFIX: &[u8] = b"p";
+const FEE_CONFIG_STORAGE_KEY: &[u8] = b"fee_config";
+const ACCRUED_FEES_STORAGE_KEY: &[u8] = b"fees";
+const RATE_LIMITS_STORAGE_KEY: &[u8] = b"rate_limits";
+const VOLUME_TO_ETHEREUM_STORAGE_KEY: &[u8] = b"volume_to_ethereum";
+const VOLUME_TO_NEAR_STORAGE_KEY: &[u8] = b"volume_to_near";
+const QUEUED_TRANSFERS_STORAGE_KEY: &[u8] = b"queued";
+const QUEUED_TRANSFERS_PREFIX: &[u8] = b"q";
+const LARGE_TRANSFER_DELAY_STORAGE_KEY: &[u8] = b"large_transfer_delay";
+const DELAYED_TRANSFERS_STORAGE_KEY: &[u8] = b"delayed";
+const DELAYED_TRANSFERS_PREFIX: &[u8] = b"d";
+const OMNI_BRIDGES_STORAGE_KEY: &[u8] = b"omni_bridges";
+const OMNI_BRIDGE_ACCOUNT_STORAGE_KEY: &[u8] = b"omni_bridge";
+const SWEPT_LIQUIDITY_STORAGE_KEY: &[u8] = b"swept";
+const TOTAL_LOCKED_STORAGE_KEY: &[u8] = b"total_locked";
+const TOTAL_MIGRATED_STORAGE_KEY: &[u8] = b"total_migrated";
+const TOTAL_FINALISED_STORAGE_KEY: &[u8] = b"total_finalised";
+const OUTSTANDING_PAYOUTS_STORAGE_KEY: &[u8] = b"outstanding_payouts";
+const SUPPLY_CHECKPOINTS_STORAGE_KEY: &[u8] = b"supply_checkpoints";
+const SUPPLY_CHECKPOINTS_PREFIX: &[u8] = b"c";
+const PROOF_RESERVATIONS_PREFIX: &[u8] = b"r";
+
+const NANOSECONDS_IN_SECOND: u64 = 1_000_000_000;
+/// Number of blocks after which a proof reserved by `finalise_eth_to_near_transfer` can be
+/// submitted again. The verification takes a few blocks, so an older reservation was left by a
+/// failed callback.
+const PROOF_RESERVATION_BLOCKS: BlockHeight = 100;
+
+/// Storage used by a proof key in `used_events`. Both the index of the key and the element of the
+/// vector take the 2 bytes prefix, the borsh encoded 32 bytes key, the 8 bytes index and the 40
+/// bytes overhead of a storage record.
+const PROOF_STORAGE_USAGE: StorageUsage = 2 * (2 + (4 + 32) + 8 + 40);
+
+/// Storage used by a queued or delayed transfer besides the recipient string. The index of the
+/// key takes the 2 bytes prefix, the borsh encoded 32 bytes key and the 8 bytes index. The key
+/// and the value in the vectors take the 2 bytes prefix and the 8 bytes index with the borsh
+/// encoded key or transfer: the 40 hex characters of the sender, the length of the recipient,
+/// the amounts, the flag and the release timestamp. Each of them takes 40 bytes overhead of a
+/// storage record.
+const QUEUED_TRANSFER_STORAGE_USAGE: StorageUsage = (2 + (4 + 32) + 8 + 40)
+    + (2 + 8 + (4 + 32) + 40)
+    + (2 + 8 + 40)
+    + (4 + 40)
+    + 4
+    + 16
+    + 16
+    + 1
+    + 8;
+
+/// Storage used by a migration recorded under its nonce besides the sender account id. The key
+/// takes the 1 byte prefix and the 8 bytes nonce, the value the borsh encoded transfer: the
+/// length of the sender, the amount, the fee, the recipient and the block height. The record
+/// takes 40 bytes overhead.
+const OUTGOING_TRANSFER_STORAGE_USAGE: StorageUsage = (1 + 8) + 4 + 16 + 16 + 20 + 8 + 40;
 
 pub type Mask = u128;
 
@@ -41,6 +137,143 @@ pub enum ResultType {
         amount: Balance,
         recipient: EthAddress,
     },
+    /// Returned instead of `MigrateNearToEthereum` once enabled with
+    /// `set_migration_result_with_nonce`, which should only be done after the eNear contract
+    /// on Ethereum is able to decode it.
+    MigrateNearToEthereumWithNonce {
+        amount: Balance,
+        recipient: EthAddress,
+        nonce: u64,
+    },
+}
+
+/// Direction of a transfer through the bridge.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde", rename_all = "snake_case")]
+pub enum Direction {
+    /// `migrate_to_ethereum`.
+    ToEthereum,
+    /// `finalise_eth_to_near_transfer`.
+    ToNear,
+}
+
+/// Migration of NEAR to Ethereum recorded under its nonce.
+#[derive(Debug, Eq, PartialEq, BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OutgoingTransfer {
+    pub sender: AccountId,
+    /// Amount to be minted on Ethereum, without the fee.
+    pub amount: U128,
+    pub fee: U128,
+    pub recipient: EthAddress,
+    pub block_height: u64,
+}
+
+/// Eth→NEAR transfer whose native NEAR payout failed, e.g. because the recipient doesn't exist.
+#[derive(Debug, Eq, PartialEq, BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PendingTransfer {
+    pub recipient: AccountId,
+    pub amount: U128,
+}
+
+/// Eth→NEAR transfer exceeding the rate limits which waits for the DAO approval.
+#[derive(Debug, Eq, PartialEq, BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct QueuedTransfer {
+    pub sender: String,
+    /// Recipient as it was passed to `transferToNear` on Ethereum.
+    pub recipient: String,
+    /// Amount burned on Ethereum, including the fee.
+    pub amount: U128,
+    pub fee: U128,
+    /// Whether the amount is already in the daily volume, i.e. the transfer was delayed and
+    /// then cancelled.
+    pub volume_recorded: bool,
+}
+
+/// Delay of the Eth→NEAR transfers of at least `threshold`, set by the DAO.
+#[derive(Debug, Eq, PartialEq, BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LargeTransferDelay {
+    /// Minimum amount of the delayed transfers, without the fee.
+    pub threshold: U128,
+    pub delay_seconds: u64,
+}
+
+/// Outcome of a proof submitted with `finalise_eth_to_near_transfers`.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FinalisationOutcome {
+    /// Hex encoded key of the event, if the proof has a valid header.
+    pub proof_key: Option<String>,
+    /// Whether the proof was recorded. The transfer might be queued or delayed.
+    pub recorded: bool,
+    /// Reason why the proof was rejected or not recorded.
+    pub error: Option<String>,
+}
+
+/// Liquidity swept to the omni bridge and the NEAR still held by the bridge.
+#[derive(Debug, Eq, PartialEq, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OmniBridgeLiquidity {
+    /// Total amount successfully transferred to the omni bridge as wNEAR.
+    pub swept: U128,
+    /// NEAR locked by the migrations to Ethereum and not released yet, see `get_total_locked`.
+    pub locked: U128,
+}
+
+/// Totals of the bridge for the proof of reserves. The locked NEAR should cover the eNear total
+/// supply from the latest checkpoint, up to the transfers in flight.
+#[derive(Debug, Eq, PartialEq, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Reserves {
+    /// Total amount minted as eNear by the migrations to Ethereum, without the fees.
+    pub total_migrated: U128,
+    /// Total amount burned on Ethereum by the finalised Eth→NEAR transfers, including the fees.
+    pub total_finalised: U128,
+    pub total_locked: U128,
+    pub swept_to_omni_bridge: U128,
+    pub accrued_fees: U128,
+    pub latest_supply_checkpoint: Option<SupplyCheckpoint>,
+}
+
+/// eNear `totalSupply` on Ethereum submitted by the DAO, with the NEAR locked at the time.
+#[derive(Debug, Clone, Eq, PartialEq, BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SupplyCheckpoint {
+    pub total_supply: U128,
+    /// Ethereum block number at which `total_supply` was read.
+    pub eth_block_number: u64,
+    pub total_locked: U128,
+    /// Timestamp of the submission in nanoseconds.
+    pub timestamp: U64,
+}
+
+/// Comparison of the tracked amounts with the account balance. The surplus includes the
+/// unclaimed refunds and pending transfers, anything sent to the account directly and the gas
+/// refunds. A deficit means the locked NEAR isn't fully backed by the balance.
+#[derive(Debug, Eq, PartialEq, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BalanceReconciliation {
+    pub account_balance: U128,
+    pub storage_cost: U128,
+    pub accrued_fees: U128,
+    pub total_locked: U128,
+    /// `account_balance - storage_cost - accrued_fees - total_locked` if it's positive.
+    pub surplus: U128,
+    /// `total_locked + accrued_fees + storage_cost - account_balance` if it's positive.
+    pub deficit: U128,
+}
+
+/// Eth→NEAR transfer which can be released with `release_delayed_transfer` after
+/// `release_timestamp` in nanoseconds, unless it is cancelled by the DAO or the pause manager.
+#[derive(Debug, Eq, PartialEq, BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DelayedTransfer {
+    #[serde(flatten)]
+    pub transfer: QueuedTransfer,
+    pub release_timestamp: U64,
 }
 
 #[derive(AccessControlRole, Deserialize, Serialize, Copy, Clone)]
@@ -52,6 +285,8 @@ pub enum Role {
     UnrestrictedFinaliseEthToNearTransfer,
     UpgradableCodeStager,
     UpgradableCodeDeployer,
+    /// May sweep the liquidity to the omni bridge set by the DAO, e.g. on a schedule.
+    LiquiditySweeper,
 }
 
 #[near_bindgen]
@@ -110,7 +345,11 @@ impl NearBridge {
     /// * `amount` must be a positive integer
     /// * Caller of the method has to attach deposit enough to cover:
     ///   * The `amount` of Near tokens being bridged, and
-    ///   * The storage difference at the fixed storage price defined in the contract.
+    ///   * The storage of the transfer recorded under its nonce, see `get_migration_deposit`, and
+    ///   * The `to_ethereum` fee.
+    ///
+    /// The storage and the `to_ethereum` fee are paid from the attached deposit, the rest of it
+    /// is bridged.
     #[payable]
     #[result_serializer(borsh)]
     // todo: how much GAS is required to execute this method with sending the tokens back and ensure we have enough
@@ -125,33 +364,349 @@ impl NearBridge {
         // If the method is paused or the eth recipient address is invalid, then we need to:
         //  1) Return the attached deposit
         //  2) Panic and tell the user why
-        let eth_recipient_clone = eth_recipient.clone();
-        if !is_valid_eth_address(eth_recipient_clone) {
-            env::panic_str("ETH address is invalid");
+        let recipient = get_eth_address(eth_recipient);
+
+        self.record_outgoing_transfer(env::predecessor_account_id(), attached_deposit, recipient)
+            .unwrap_or_else(|error| env::panic_str(error))
+    }
+
+    /// Migrates wNEAR transferred with `ft_transfer_call` to Ethereum. `msg` is the eth
+    /// recipient. The wNEAR is unwrapped and the migration is recorded by
+    /// `finish_wnear_migration`, whose result is the same `ResultType` as the one of
+    /// `migrate_to_ethereum`. All the tokens are always used, since they are unwrapped in a
+    /// separate receipt.
+    /// It is paused together with `migrate_to_ethereum`, with the same exemptions applied to
+    /// the sender of the tokens.
+    pub fn ft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        amount: U128,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        if Some(env::predecessor_account_id()) != self.get_wnear_account_id() {
+            env::panic_str("Only wNEAR can be migrated to Ethereum");
+        }
+        if self.pa_is_paused("migrate_to_ethereum".to_owned())
+            && !self.acl_has_any_role(
+                vec![Role::DAO.into(), Role::UnrestrictedMigrateToEthereum.into()],
+                sender_id.clone(),
+            )
+        {
+            env::panic_str("Pausable: Method is paused");
+        }
+        let recipient = get_eth_address(msg);
+
+        ext_wnear_token::ext(env::predecessor_account_id())
+            .with_static_gas(WNEAR_WITHDRAW_GAS)
+            .with_attached_deposit(ONE_YOCTO)
+            .near_withdraw(amount)
+            .then(
+                ext_self::ext(env::current_account_id())
+                    .with_static_gas(FINISH_WNEAR_MIGRATION_GAS)
+                    .finish_wnear_migration(sender_id, amount, recipient),
+            );
+
+        PromiseOrValue::Value(U128(0))
+    }
+
+    /// Records the migration of the unwrapped wNEAR. Can only be called by the contract itself.
+    /// If the migration can't be recorded, e.g. because the rate limit is exceeded, the sender
+    /// gets back the NEAR, or the wNEAR if it wasn't unwrapped.
+    #[result_serializer(borsh)]
+    pub fn finish_wnear_migration(
+        &mut self,
+        #[callback_result]
+        #[serializer(borsh)]
+        withdraw_result: Result<(), PromiseError>,
+        #[serializer(borsh)] sender_id: AccountId,
+        #[serializer(borsh)] amount: U128,
+        #[serializer(borsh)] recipient: EthAddress,
+    ) -> PromiseOrValue<ResultType> {
+        near_sdk::assert_self();
+
+        let error = match withdraw_result {
+            Ok(()) => match self.record_outgoing_transfer(sender_id.clone(), amount.0, recipient) {
+                Ok(result) => return PromiseOrValue::Value(result),
+                Err(error) => error,
+            },
+            Err(_) => "Failed to unwrap wNEAR",
+        };
+
+        Event::MigrateToEthereumFailed {
+            sender: sender_id.clone(),
+            amount,
+            reason: error.to_owned(),
+        }
+        .emit();
+
+        match withdraw_result {
+            Ok(()) => Promise::new(sender_id).transfer(amount.0).into(),
+            Err(_) => {
+                let wnear_account_id = self
+                    .get_wnear_account_id()
+                    .unwrap_or_else(|| env::panic_str("WNear address hasn't been set"));
+                ext_wnear_token::ext(wnear_account_id)
+                    .with_static_gas(FT_TRANSFER_GAS)
+                    .with_attached_deposit(ONE_YOCTO)
+                    .ft_transfer(sender_id, amount, None)
+                    .into()
+            }
+        }
+    }
+
+    /// Records the migration under the next nonce, paying for its storage and the fee from
+    /// `deposit`. Nothing is recorded if `deposit` is not sufficient or the rate limit is
+    /// exceeded.
+    fn record_outgoing_transfer(
+        &mut self,
+        sender: AccountId,
+        deposit: Balance,
+        recipient: EthAddress,
+    ) -> Result<ResultType, &'static str> {
+        let storage_cost = self.get_migration_deposit(sender.clone()).0;
+        if deposit <= storage_cost {
+            return Err("Attached deposit is not sufficient to cover the storage");
+        }
+        let amount = deposit - storage_cost;
+        let fee = self.get_fee_config().to_ethereum.compute(amount);
+        if amount <= fee {
+            return Err("Attached deposit is not sufficient to cover the fee");
+        }
+        let amount = amount - fee;
+        self.record_volume(Direction::ToEthereum, amount)
+            .map_err(|error| error.as_str())?;
+
+        let nonce = self.get_outgoing_nonce() + 1;
+        let transfer = OutgoingTransfer {
+            sender,
+            amount: U128(amount),
+            fee: U128(fee),
+            recipient,
+            block_height: env::block_height(),
+        };
+        Self::outgoing_transfers().insert(&nonce, &transfer);
+        write_state(OUTGOING_NONCE_STORAGE_KEY, &nonce);
+        Self::accrue_fee(fee);
+        Self::add_locked(amount);
+        add_to_state(TOTAL_MIGRATED_STORAGE_KEY, amount);
+
+        Event::MigrateToEthereum {
+            sender: transfer.sender,
+            amount: transfer.amount,
+            fee: transfer.fee,
+            recipient: recipient.to_string(),
+            nonce,
+            block_height: transfer.block_height,
         }
+        .emit();
 
-        ResultType::MigrateNearToEthereum {
-            amount: attached_deposit,
-            recipient: get_eth_address(eth_recipient),
+        if self.get_migration_result_with_nonce() {
+            Ok(ResultType::MigrateNearToEthereumWithNonce {
+                amount,
+                recipient,
+                nonce,
+            })
+        } else {
+            Ok(ResultType::MigrateNearToEthereum { amount, recipient })
         }
     }
 
+    /// Returns the part of the migrated amount which pays for the storage of the migration of
+    /// `sender`. It isn't bridged.
+    pub fn get_migration_deposit(&self, sender: AccountId) -> U128 {
+        let storage_usage = OUTGOING_TRANSFER_STORAGE_USAGE + sender.as_str().len() as StorageUsage;
+        U128(Balance::from(storage_usage) * env::storage_byte_cost())
+    }
+
+    /// Returns the migration to Ethereum with the given nonce.
+    pub fn get_outgoing_transfer(&self, nonce: u64) -> Option<OutgoingTransfer> {
+        Self::outgoing_transfers().get(&nonce)
+    }
+
+    fn outgoing_transfers() -> LookupMap<u64, OutgoingTransfer> {
+        LookupMap::new(OUTGOING_TRANSFERS_PREFIX)
+    }
+
+    /// Switches `migrate_to_ethereum` to return `ResultType::MigrateNearToEthereumWithNonce`.
+    #[access_control_any(roles(Role::DAO))]
+    pub fn set_migration_result_with_nonce(&mut self, enabled: bool) {
+        write_state(RESULT_WITH_NONCE_STORAGE_KEY, &enabled);
+    }
+
+    pub fn get_migration_result_with_nonce(&self) -> bool {
+        read_state(RESULT_WITH_NONCE_STORAGE_KEY).unwrap_or_default()
+    }
+
     #[payable]
     #[pause(except(roles(Role::DAO, Role::UnrestrictedFinaliseEthToNearTransfer)))]
     pub fn finalise_eth_to_near_transfer(&mut self, #[serializer(borsh)] proof: Proof) -> Promise {
-        let event = TransferToNearInitiatedEvent::from_log_entry_data(&proof.log_entry_data);
-        assert_eq!(
-            event.e_near_address,
-            self.e_near_address,
-            "Event's address {} does not match locker address of this token {}",
-            hex::encode(&event.e_near_address),
-            hex::encode(&self.e_near_address),
-        );
+        self.start_finalisation(
+            proof,
+            env::attached_deposit(),
+            VERIFY_LOG_ENTRY_GAS,
+            FINISH_FINALISE_GAS,
+        )
+        .unwrap_or_else(|error| env::panic_str(error))
+    }
 
-        let proof_1 = proof.clone();
+    /// Finalises several events in one transaction, e.g. the events of one receipt with their log
+    /// indexes. Each proof is verified and recorded independently, so a rejected proof doesn't
+    /// revert the others. The attached deposit is split evenly between the proofs and the surplus
+    /// is returned as with `finalise_eth_to_near_transfer`.
+    /// Each proof takes `BATCH_START_FINALISATION_GAS`, `VERIFY_LOG_ENTRY_GAS` and the gas to pay
+    /// out its transfer, i.e. 100 TGas for a native transfer and up to 195 TGas for a transfer via
+    /// wNEAR, Aurora or an omni bridge. So 300 TGas fit two native transfers but only one via
+    /// wNEAR. Rejected proofs don't take any gas. The rest of the prepaid gas is split between the
+    /// proofs. A transfer whose payout doesn't fit into its share of the gas is not finalised, so
+    /// the proof can be submitted again with more gas. Returns the outcomes in the order of the
+    /// proofs. Paused together with `finalise_eth_to_near_transfer`.
+    #[payable]
+    #[pause(
+        name = "finalise_eth_to_near_transfer",
+        except(roles(Role::DAO, Role::UnrestrictedFinaliseEthToNearTransfer))
+    )]
+    pub fn finalise_eth_to_near_transfers(
+        &mut self,
+        #[serializer(borsh)] proofs: Vec<Proof>,
+    ) -> PromiseOrValue<Vec<FinalisationOutcome>> {
+        if proofs.is_empty() {
+            env::panic_str("No proofs to finalise");
+        }
+        let available_gas = env::prepaid_gas().0 - env::used_gas().0;
+        let mut required_gas = REPORT_FINALISATION_GAS.0;
+        let deposit = env::attached_deposit() / proofs.len() as Balance;
+        let mut used_deposit = 0;
+        let mut finalisations: Option<Promise> = None;
+        let mut outcomes = Vec::with_capacity(proofs.len());
+        for proof in proofs {
+            let proof_key = proof.get_key().ok().map(hex::encode);
+            let finish_gas = Self::batch_finish_gas(&proof);
+            let error =
+                match self.start_finalisation(proof, deposit, VERIFY_LOG_ENTRY_GAS, finish_gas) {
+                    Ok(finalisation) => {
+                        used_deposit += deposit;
+                        required_gas +=
+                            BATCH_START_FINALISATION_GAS.0 + VERIFY_LOG_ENTRY_GAS.0 + finish_gas.0;
+                        finalisations = Some(match finalisations {
+                            Some(finalisations) => finalisations.and(finalisation),
+                            None => finalisation,
+                        });
+                        None
+                    }
+                    Err(error) => Some(error.to_owned()),
+                };
+            outcomes.push(FinalisationOutcome {
+                proof_key,
+                recorded: false,
+                error,
+            });
+        }
+        if available_gas < required_gas {
+            env::panic_str("Not enough gas to finalise the proofs");
+        }
+        // Deposits of the rejected proofs.
+        Self::refund_deposit_surplus(env::predecessor_account_id(), used_deposit);
+
+        match finalisations {
+            Some(finalisations) => finalisations
+                .then(
+                    ext_self::ext(env::current_account_id())
+                        .with_static_gas(REPORT_FINALISATION_GAS)
+                        .with_unused_gas_weight(0)
+                        .report_finalisation(outcomes),
+                )
+                .into(),
+            None => PromiseOrValue::Value(outcomes),
+        }
+    }
+
+    /// Fills in the outcomes of the proofs submitted by `finalise_eth_to_near_transfers` from the
+    /// results of their finalisations, in the same order. Can only be called by the contract
+    /// itself.
+    pub fn report_finalisation(
+        &self,
+        outcomes: Vec<FinalisationOutcome>,
+    ) -> Vec<FinalisationOutcome> {
+        near_sdk::assert_self();
+
+        let mut promise_index = 0;
+        outcomes
+            .into_iter()
+            .map(|mut outcome| {
+                if outcome.error.is_some() {
+                    return outcome;
+                }
+                outcome.recorded = matches!(
+                    &outcome.proof_key,
+                    Some(proof_key) if self.used_events.contains(&Self::decode_proof_key(proof_key))
+                );
+                if !outcome.recorded {
+                    // `finish_eth_to_near_transfer` returns why it didn't record the proof.
+                    let reason = match env::promise_result(promise_index) {
+                        PromiseResult::Successful(result) => {
+                            near_sdk::serde_json::from_slice::<Option<String>>(&result)
+                                .ok()
+                                .flatten()
+                        }
+                        _ => None,
+                    };
+                    outcome.error = Some(
+                        reason.unwrap_or_else(|| "Failed to finalise the transfer".to_owned()),
+                    );
+                }
+                promise_index += 1;
+                outcome
+            })
+            .collect()
+    }
+
+    /// Gas for `finish_eth_to_near_transfer` to pay out the transfer of a proof submitted with
+    /// `finalise_eth_to_near_transfers`. A proof without a valid recipient is only given the
+    /// overhead, since it is rejected anyway.
+    fn batch_finish_gas(proof: &Proof) -> Gas {
+        let delivery_gas = TransferToNearInitiatedEvent::from_log_entry_data(&proof.log_entry_data)
+            .ok()
+            .and_then(|event| parse_recipient(&event.recipient))
+            .map_or(Gas(0), |recipient| Self::delivery_gas(&recipient.delivery));
+        FINISH_FINALISE_OVERHEAD_GAS + delivery_gas
+    }
 
-        ext_prover::ext(self.prover_account.clone())
-            .with_static_gas(VERIFY_LOG_ENTRY_GAS)
+    /// Checks the proof and schedules its verification followed by `finish_eth_to_near_transfer`
+    /// with `deposit` attached. The event is reserved until then. Both calls get the unused gas
+    /// in addition to the static gas, most of it goes to the payout.
+    fn start_finalisation(
+        &mut self,
+        proof: Proof,
+        deposit: Balance,
+        verify_gas: Gas,
+        finish_gas: Gas,
+    ) -> Result<Promise, &'static str> {
+        let event = TransferToNearInitiatedEvent::from_log_entry_data(&proof.log_entry_data)
+            .map_err(|error| error.as_str())?;
+        if event.e_near_address != self.e_near_address {
+            return Err(BridgeError::WrongEventAddress.as_str());
+        }
+
+        let proof_key = proof.get_key().map_err(|error| error.as_str())?;
+        if self.used_events.contains(&proof_key)
+            || self.used_events.contains(&proof.get_legacy_key())
+        {
+            return Err(BridgeError::ProofAlreadyUsed.as_str());
+        }
+        // Reserved until the callback, so a concurrent submission doesn't pay for the
+        // verification of the same event.
+        let mut proof_reservations = Self::proof_reservations();
+        if matches!(
+            proof_reservations.get(&proof_key),
+            Some(reserved_at) if env::block_height() < reserved_at + PROOF_RESERVATION_BLOCKS
+        ) {
+            return Err(BridgeError::ProofBeingFinalised.as_str());
+        }
+        proof_reservations.insert(&proof_key, &env::block_height());
+
+        let finalisation = ext_prover::ext(self.prover_account.clone())
+            .with_static_gas(verify_gas)
+            .with_unused_gas_weight(1)
             .verify_log_entry(
                 proof.log_index,
                 proof.log_entry_data,
@@ -163,40 +718,289 @@ impl NearBridge {
             )
             .then(
                 ext_self::ext(env::current_account_id())
-                    .with_static_gas(FINISH_FINALISE_GAS)
-                    .with_attached_deposit(env::attached_deposit())
-                    .finish_eth_to_near_transfer(event.recipient, event.amount, proof_1),
-            )
+                    .with_static_gas(finish_gas)
+                    .with_unused_gas_weight(2)
+                    .with_attached_deposit(deposit)
+                    .finish_eth_to_near_transfer(
+                        event.recipient,
+                        event.amount,
+                        event.sender,
+                        proof_key,
+                        env::predecessor_account_id(),
+                    ),
+            );
+        Ok(finalisation)
     }
 
     /// Finish depositing once the proof was successfully validated. Can only be called by the contract
     /// itself.
+    /// The `to_near` fee is deducted from the transferred amount.
+    /// If the transfer exceeds the `to_near` rate limit, it is rejected or, if enabled, queued
+    /// for the DAO approval. Transfers above the large transfer threshold are delayed.
+    /// If the proof was not verified or the transfer can't be finalised, e.g. because the fee
+    /// isn't covered, the rate limit is exceeded or the deposit or gas is not sufficient, the
+    /// `finalisation_failed` event is emitted and nothing is recorded, so the same proof can be
+    /// submitted again.
+    /// The part of the attached deposit which isn't used for the storage is returned to
+    /// `relayer_id`, the caller of `finalise_eth_to_near_transfer`. Queued and delayed transfers
+    /// also take `get_queue_deposit`.
+    /// Returns the reason why the transfer wasn't finalised, if any.
     #[payable]
     pub fn finish_eth_to_near_transfer(
         &mut self,
-        #[callback]
+        #[callback_result]
         #[serializer(borsh)]
-        verification_success: bool,
+        verification_result: Result<bool, PromiseError>,
         #[serializer(borsh)] new_owner_id: String,
         #[serializer(borsh)] amount: Balance,
-        #[serializer(borsh)] proof: Proof,
-    ) -> Promise {
+        #[serializer(borsh)] sender: String,
+        #[serializer(borsh)] proof_key: Vec<u8>,
+        #[serializer(borsh)] relayer_id: AccountId,
+    ) -> PromiseOrValue<Option<String>> {
         near_sdk::assert_self();
-        assert!(verification_success, "Failed to verify the proof");
+        Self::proof_reservations().remove(&proof_key);
 
-        let required_deposit = self.record_proof(&proof);
+        let fee = self.get_fee_config().to_near.compute(amount);
+        let transfer = TransferToNear {
+            proof_key: hex::encode(&proof_key),
+            sender,
+            amount: U128(amount),
+            fee: U128(fee),
+            recipient: new_owner_id.clone(),
+        };
+
+        if !matches!(verification_result, Ok(true)) {
+            return Self::fail_finalisation(transfer, "Failed to verify the proof", relayer_id);
+        }
+        // Another submission of the same event might have been verified after its reservation
+        // expired.
+        if self.used_events.contains(&proof_key) {
+            return Self::fail_finalisation(
+                transfer,
+                BridgeError::ProofAlreadyUsed.as_str(),
+                relayer_id,
+            );
+        }
+
+        // The proof is not recorded if the transfer can't be finalised, e.g. if the fee can't be
+        // paid, so it can be submitted again once that is resolved.
+        let mut required_deposit = self.get_proof_deposit().0;
         if env::attached_deposit() < required_deposit {
-            env::panic_str("Attached deposit is not sufficient to record proof");
+            return Self::fail_finalisation(
+                transfer,
+                "Attached deposit is not sufficient to record proof",
+                relayer_id,
+            );
+        }
+        if amount <= fee {
+            return Self::fail_finalisation(
+                transfer,
+                "Transferred amount is not sufficient to cover the fee",
+                relayer_id,
+            );
+        }
+        let recipient = match parse_recipient(&new_owner_id) {
+            Some(recipient) => recipient,
+            None => {
+                return Self::fail_finalisation(
+                    transfer,
+                    BridgeError::InvalidRecipient.as_str(),
+                    relayer_id,
+                )
+            }
+        };
+        if matches!(recipient.delivery, Delivery::OmniBridge { .. })
+            && !self.get_omni_bridges().contains(&recipient.target)
+        {
+            return Self::fail_finalisation(
+                transfer,
+                BridgeError::OmniBridgeNotWhitelisted.as_str(),
+                relayer_id,
+            );
+        }
+
+        let volume_result = self.check_volume(Direction::ToNear, amount - fee);
+        if let Err(error) = volume_result {
+            if !self.get_rate_limits().queue_exceeding_transfers {
+                return Self::fail_finalisation(transfer, error.as_str(), relayer_id);
+            }
+        }
+        let delay = self
+            .get_large_transfer_delay()
+            .filter(|delay| volume_result.is_ok() && amount - fee >= delay.threshold.0);
+        if volume_result.is_err() || delay.is_some() {
+            required_deposit += self.get_queue_deposit(new_owner_id.clone()).0;
+            if env::attached_deposit() < required_deposit {
+                return Self::fail_finalisation(
+                    transfer,
+                    "Attached deposit is not sufficient to queue the transfer",
+                    relayer_id,
+                );
+            }
+        } else if env::prepaid_gas().0
+            < env::used_gas().0
+                + FINISH_FINALISE_OVERHEAD_GAS.0
+                + Self::delivery_gas(&recipient.delivery).0
+        {
+            return Self::fail_finalisation(
+                transfer,
+                "Not enough gas to pay out the transfer",
+                relayer_id,
+            );
+        }
+
+        self.record_proof(&proof_key);
+        Self::accrue_fee(fee);
+        // The burned amount isn't locked anymore, even if the payout is queued or delayed.
+        Self::sub_locked(amount);
+        add_to_state(TOTAL_FINALISED_STORAGE_KEY, amount);
+        Self::refund_deposit_surplus(relayer_id, required_deposit);
+
+        let queued_transfer = QueuedTransfer {
+            sender: transfer.sender.clone(),
+            recipient: new_owner_id,
+            amount: transfer.amount,
+            fee: transfer.fee,
+            volume_recorded: volume_result.is_ok(),
+        };
+        if volume_result.is_err() {
+            let mut queued_transfers = Self::queued_transfers();
+            queued_transfers.insert(&proof_key, &queued_transfer);
+            write_state(QUEUED_TRANSFERS_STORAGE_KEY, &queued_transfers);
+            add_to_state(OUTSTANDING_PAYOUTS_STORAGE_KEY, amount - fee);
+
+            Event::TransferQueued(transfer).emit();
+            return PromiseOrValue::Value(None);
+        }
+
+        Self::add_volume(Direction::ToNear, amount - fee);
+        let delay = match delay {
+            Some(delay) => delay,
+            None => return self.deliver_transfer(transfer, recipient).into(),
+        };
+
+        let release_timestamp =
+            U64(env::block_timestamp() + delay.delay_seconds * NANOSECONDS_IN_SECOND);
+        let mut delayed_transfers = Self::delayed_transfers();
+        delayed_transfers.insert(
+            &proof_key,
+            &DelayedTransfer {
+                transfer: queued_transfer,
+                release_timestamp,
+            },
+        );
+        write_state(DELAYED_TRANSFERS_STORAGE_KEY, &delayed_transfers);
+        add_to_state(OUTSTANDING_PAYOUTS_STORAGE_KEY, amount - fee);
+
+        Event::TransferDelayed {
+            transfer,
+            release_timestamp,
+        }
+        .emit();
+        PromiseOrValue::Value(None)
+    }
+
+    /// Emits the reason why the transfer wasn't finalised and returns the attached deposit to the
+    /// relayer. Nothing is recorded, so the proof can be submitted again. Returns the reason.
+    fn fail_finalisation(
+        transfer: TransferToNear,
+        reason: &str,
+        relayer_id: AccountId,
+    ) -> PromiseOrValue<Option<String>> {
+        Event::FinalisationFailed {
+            transfer,
+            reason: reason.to_owned(),
+        }
+        .emit();
+        Self::refund_deposit_surplus(relayer_id, 0);
+        PromiseOrValue::Value(Some(reason.to_owned()))
+    }
+
+    /// Block heights of the proofs being verified by their keys.
+    fn proof_reservations() -> LookupMap<Vec<u8>, BlockHeight> {
+        LookupMap::new(PROOF_RESERVATIONS_PREFIX)
+    }
+
+    /// Returns the part of the attached deposit exceeding `used_deposit` to the relayer.
+    fn refund_deposit_surplus(relayer_id: AccountId, used_deposit: Balance) {
+        let surplus = env::attached_deposit().saturating_sub(used_deposit);
+        if surplus > 0 {
+            Promise::new(relayer_id).transfer(surplus);
+        }
+    }
+
+    /// Gas attached to the calls scheduled by `deliver_transfer`.
+    fn delivery_gas(delivery: &Delivery) -> Gas {
+        match delivery {
+            Delivery::WnearTransferCall { .. } | Delivery::OmniBridge { .. } => {
+                WNEAR_DEPOSIT_GAS + FT_TRANSFER_CALL_GAS + RESOLVE_WNEAR_TRANSFER_CALL_GAS
+            }
+            Delivery::Wnear { .. } => STORAGE_BALANCE_OF_GAS + SEND_WNEAR_TRANSFER_GAS,
+            Delivery::Aurora { .. } => {
+                WNEAR_DEPOSIT_GAS + FT_TRANSFER_CALL_GAS + RESOLVE_AURORA_DEPOSIT_GAS
+            }
+            Delivery::Native => RESOLVE_NATIVE_TRANSFER_GAS,
         }
+    }
 
-        let Recipient { target, message } = parse_recipient(&new_owner_id)
-            .unwrap_or_else(|| env::panic_str("Failed to parse recipient"));
+    /// Pays out the transferred amount without the fee as requested by the recipient.
+    fn deliver_transfer(&self, transfer: TransferToNear, recipient: Recipient) -> Promise {
+        let Recipient {
+            target,
+            delivery,
+            refund_to,
+        } = recipient;
+        let amount = transfer.amount.0 - transfer.fee.0;
 
-        match message {
-            Some(message) => {
+        match delivery {
+            Delivery::WnearTransferCall { msg, memo } | Delivery::OmniBridge { msg, memo } => {
+                let wnear_account_id = self
+                    .get_wnear_account_id()
+                    .unwrap_or_else(|| env::panic_str("WNear address hasn't been set"));
+                let proof_key = transfer.proof_key.clone();
+                Event::FinalisedViaWnear(transfer).emit();
+                ext_wnear_token::ext(wnear_account_id.clone())
+                    .with_static_gas(WNEAR_DEPOSIT_GAS)
+                    .with_attached_deposit(amount)
+                    .near_deposit()
+                    .then(
+                        ext_wnear_token::ext(wnear_account_id)
+                            .with_static_gas(FT_TRANSFER_CALL_GAS)
+                            .with_attached_deposit(ONE_YOCTO)
+                            .ft_transfer_call(target.clone(), amount.into(), memo, msg),
+                    )
+                    .then(
+                        ext_self::ext(env::current_account_id())
+                            .with_static_gas(RESOLVE_WNEAR_TRANSFER_CALL_GAS)
+                            .resolve_wnear_transfer_call(
+                                target,
+                                amount.into(),
+                                proof_key,
+                                refund_to,
+                            ),
+                    )
+            }
+            Delivery::Wnear { memo } => {
+                let wnear_account_id = self
+                    .get_wnear_account_id()
+                    .unwrap_or_else(|| env::panic_str("WNear address hasn't been set"));
+                let proof_key = transfer.proof_key.clone();
+                Event::FinalisedViaWnear(transfer).emit();
+                ext_wnear_token::ext(wnear_account_id)
+                    .with_static_gas(STORAGE_BALANCE_OF_GAS)
+                    .storage_balance_of(target.clone())
+                    .then(
+                        ext_self::ext(env::current_account_id())
+                            .with_static_gas(SEND_WNEAR_TRANSFER_GAS)
+                            .send_wnear_transfer(target, amount.into(), proof_key, memo, refund_to),
+                    )
+            }
+            Delivery::Aurora { address } => {
                 let wnear_account_id = self
                     .get_wnear_account_id()
                     .unwrap_or_else(|| env::panic_str("WNear address hasn't been set"));
+                let proof_key = transfer.proof_key.clone();
+                Event::FinalisedViaWnear(transfer).emit();
                 ext_wnear_token::ext(wnear_account_id.clone())
                     .with_static_gas(WNEAR_DEPOSIT_GAS)
                     .with_attached_deposit(amount)
@@ -205,214 +1009,3671 @@ impl NearBridge {
                         ext_wnear_token::ext(wnear_account_id)
                             .with_static_gas(FT_TRANSFER_CALL_GAS)
                             .with_attached_deposit(ONE_YOCTO)
-                            .ft_transfer_call(target, amount.into(), None, message),
+                            .ft_transfer_call(
+                                target.clone(),
+                                amount.into(),
+                                None,
+                                hex::encode(address),
+                            ),
+                    )
+                    .then(
+                        ext_self::ext(env::current_account_id())
+                            .with_static_gas(RESOLVE_AURORA_DEPOSIT_GAS)
+                            .resolve_aurora_deposit(target, amount.into(), proof_key),
                     )
             }
-            None => Promise::new(target).transfer(amount),
+            Delivery::Native => {
+                let proof_key = transfer.proof_key.clone();
+                Event::Finalised(transfer).emit();
+                Self::send_native_transfer(target, amount, proof_key, refund_to)
+            }
         }
     }
 
-    pub fn get_avialable_balance(&self) -> U128 {
-        U128(
-            env::account_balance()
-                - env::attached_deposit()
-                - env::storage_byte_cost() * env::storage_usage() as u128,
-        )
-    }
+    /// Sends the wNEAR with `ft_transfer`. Can only be called by the contract itself.
+    /// If the recipient is not registered on the wNEAR contract, it is registered with the
+    /// storage deposit taken from the amount. If the amount doesn't cover the storage deposit,
+    /// it is sent as native NEAR instead.
+    pub fn send_wnear_transfer(
+        &mut self,
+        #[callback_result] storage_balance: Result<Option<StorageBalance>, PromiseError>,
+        recipient: AccountId,
+        amount: U128,
+        proof_key: String,
+        memo: Option<String>,
+        refund_to: Option<AccountId>,
+    ) -> Promise {
+        near_sdk::assert_self();
 
-    #[access_control_any(roles(Role::DAO))]
-    #[payable]
-    pub fn send_to_omni_bridge(&mut self, omni_bridge: AccountId) -> Promise {
-        let amount = self.get_avialable_balance().0;
         let wnear_account_id = self
             .get_wnear_account_id()
             .unwrap_or_else(|| env::panic_str("WNear address hasn't been set"));
+        let mut amount = amount.0;
+        let registration = if matches!(storage_balance, Ok(Some(_))) {
+            None
+        } else if amount > WNEAR_STORAGE_DEPOSIT {
+            amount -= WNEAR_STORAGE_DEPOSIT;
+            Some(
+                ext_wnear_token::ext(wnear_account_id.clone())
+                    .with_static_gas(WNEAR_STORAGE_DEPOSIT_GAS)
+                    .with_attached_deposit(WNEAR_STORAGE_DEPOSIT)
+                    .storage_deposit(recipient.clone(), Some(true)),
+            )
+        } else {
+            return Self::send_native_transfer(recipient, amount, proof_key, refund_to);
+        };
 
-        ext_wnear_token::ext(wnear_account_id.clone())
+        let deposit = ext_wnear_token::ext(wnear_account_id.clone())
             .with_static_gas(WNEAR_DEPOSIT_GAS)
             .with_attached_deposit(amount)
-            .near_deposit()
+            .near_deposit();
+        let deposit = match registration {
+            Some(registration) => registration.then(deposit),
+            None => deposit,
+        };
+        deposit
             .then(
                 ext_wnear_token::ext(wnear_account_id)
                     .with_static_gas(FT_TRANSFER_GAS)
                     .with_attached_deposit(ONE_YOCTO)
-                    .ft_transfer(omni_bridge, amount.into(), None),
+                    .ft_transfer(recipient.clone(), amount.into(), memo),
+            )
+            .then(
+                ext_self::ext(env::current_account_id())
+                    .with_static_gas(RESOLVE_WNEAR_TRANSFER_GAS)
+                    .resolve_wnear_transfer(recipient, amount.into(), proof_key, refund_to),
             )
     }
 
-    /// Checks whether the provided proof is already used
-    pub fn is_used_proof(&self, #[serializer(borsh)] proof: Proof) -> bool {
-        self.used_events.contains(&proof.get_key())
-    }
-
-    /// Record proof to make sure it is not re-used later for anther deposit.
-    fn record_proof(&mut self, proof: &Proof) -> Balance {
-        // TODO: Instead of sending the full proof (clone only relevant parts of the Proof)
-        //       log_index / receipt_index / header_data
+    /// Records the transfer as pending for `refund_to`, or the recipient if not set, if the wNEAR
+    /// transfer to the recipient failed. The wNEAR is unwrapped, so the pending transfer is paid
+    /// out as native NEAR. Can only be called by the contract itself.
+    pub fn resolve_wnear_transfer(
+        &mut self,
+        #[callback_result]
+        #[serializer(borsh)]
+        transfer_result: Result<(), PromiseError>,
+        recipient: AccountId,
+        amount: U128,
+        proof_key: String,
+        refund_to: Option<AccountId>,
+    ) -> bool {
         near_sdk::assert_self();
-        let initial_storage = env::storage_usage();
-        let proof_key = proof.get_key();
-        assert!(
-            !self.used_events.contains(&proof_key),
-            "Event cannot be reused for depositing."
-        );
-        self.used_events.insert(&proof_key);
-        let current_storage = env::storage_usage();
 
-        let required_deposit =
-            Balance::from(current_storage - initial_storage) * env::storage_byte_cost();
+        if transfer_result.is_ok() {
+            return true;
+        }
 
-        required_deposit
+        self.withdraw_wnear(amount.0);
+        Self::add_pending_transfer(proof_key, refund_to.unwrap_or(recipient), amount);
+        false
     }
 
-    #[payable]
+    /// Returns the queued transfer of the event with the given hex encoded proof key.
+    pub fn get_queued_transfer(&self, proof_key: String) -> Option<QueuedTransfer> {
+        Self::queued_transfers().get(&Self::decode_proof_key(&proof_key))
+    }
+
+    /// Returns the queued transfers as pairs of hex encoded proof key and transfer.
+    pub fn get_queued_transfers(
+        &self,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<(String, QueuedTransfer)> {
+        Self::queued_transfers()
+            .iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .map(|(key, transfer)| (hex::encode(key), transfer))
+            .collect()
+    }
+
+    /// Pays out the queued transfer. Its amount is added to the daily volume regardless of the
+    /// limit, unless it's already there.
     #[access_control_any(roles(Role::DAO))]
-    pub fn set_wnear_account_id(&mut self, wnear: AccountId) -> Promise {
-        env::storage_write(WNEAR_STORAGE_KEY, &wnear.try_to_vec().unwrap());
+    pub fn approve_queued_transfer(&mut self, proof_key: String) -> Promise {
+        let key = Self::decode_proof_key(&proof_key);
+        let mut queued_transfers = Self::queued_transfers();
+        let queued_transfer = queued_transfers
+            .remove(&key)
+            .unwrap_or_else(|| env::panic_str("Queued transfer not found"));
+        write_state(QUEUED_TRANSFERS_STORAGE_KEY, &queued_transfers);
 
-        ext_wnear_token::ext(wnear)
-            .with_static_gas(WNEAR_STORAGE_DEPOSIT_GAS)
-            .with_attached_deposit(env::attached_deposit())
-            .storage_deposit(env::current_account_id())
+        let recipient = parse_recipient(&queued_transfer.recipient)
+            .unwrap_or_else(|| env::panic_str(BridgeError::InvalidRecipient.as_str()));
+        let amount = queued_transfer.amount.0 - queued_transfer.fee.0;
+        sub_from_state(OUTSTANDING_PAYOUTS_STORAGE_KEY, amount);
+        if !queued_transfer.volume_recorded {
+            Self::add_volume(Direction::ToNear, amount);
+        }
+
+        self.deliver_transfer(
+            TransferToNear {
+                proof_key,
+                sender: queued_transfer.sender,
+                amount: queued_transfer.amount,
+                fee: queued_transfer.fee,
+                recipient: queued_transfer.recipient,
+            },
+            recipient,
+        )
     }
 
-    pub fn get_wnear_account_id(&self) -> Option<AccountId> {
-        AccountId::try_from_slice(&env::storage_read(WNEAR_STORAGE_KEY)?).ok()
+    pub fn get_large_transfer_delay(&self) -> Option<LargeTransferDelay> {
+        read_state(LARGE_TRANSFER_DELAY_STORAGE_KEY)
     }
 
+    /// Sets the delay of the Eth→NEAR transfers above the threshold, disables it if not set.
     #[access_control_any(roles(Role::DAO))]
-    pub fn attach_full_access_key(&self, public_key: PublicKey) -> Promise {
-        Promise::new(env::current_account_id()).add_full_access_key(public_key)
+    pub fn set_large_transfer_delay(&mut self, delay: Option<LargeTransferDelay>) {
+        match delay {
+            Some(delay) => write_state(LARGE_TRANSFER_DELAY_STORAGE_KEY, &delay),
+            None => {
+                env::storage_remove(LARGE_TRANSFER_DELAY_STORAGE_KEY);
+            }
+        }
     }
 
-    pub fn version(&self) -> String {
-        env!("CARGO_PKG_VERSION").to_owned()
+    /// Returns the delayed transfer of the event with the given hex encoded proof key.
+    pub fn get_delayed_transfer(&self, proof_key: String) -> Option<DelayedTransfer> {
+        Self::delayed_transfers().get(&Self::decode_proof_key(&proof_key))
     }
-}
-
-#[ext_contract(ext_self)]
-pub trait ExtNearBridge {
-    #[result_serializer(borsh)]
-    fn finish_eth_to_near_transfer(
-        &mut self,
-        #[callback]
-        #[serializer(borsh)]
-        verification_success: bool,
-        #[serializer(borsh)] new_owner_id: String,
-        #[serializer(borsh)] amount: Balance,
-        #[serializer(borsh)] proof: Proof,
-    ) -> Promise;
-}
 
-#[ext_contract(ext_wnear_token)]
-pub trait ExtWNearToken {
-    fn ft_transfer_call(
+    /// Returns the delayed transfers as pairs of hex encoded proof key and transfer.
+    pub fn get_delayed_transfers(
         &self,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<(String, DelayedTransfer)> {
+        Self::delayed_transfers()
+            .iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .map(|(key, transfer)| (hex::encode(key), transfer))
+            .collect()
+    }
+
+    /// Pays out the delayed transfer once its release timestamp has passed. Can be called by
+    /// anyone.
+    #[pause(except(roles(Role::DAO)))]
+    pub fn release_delayed_transfer(&mut self, proof_key: String) -> Promise {
+        let key = Self::decode_proof_key(&proof_key);
+        let mut delayed_transfers = Self::delayed_transfers();
+        let delayed_transfer = delayed_transfers
+            .remove(&key)
+            .unwrap_or_else(|| env::panic_str("Delayed transfer not found"));
+        if env::block_timestamp() < delayed_transfer.release_timestamp.0 {
+            env::panic_str("Delayed transfer can't be released yet");
+        }
+        write_state(DELAYED_TRANSFERS_STORAGE_KEY, &delayed_transfers);
+
+        let transfer = delayed_transfer.transfer;
+        let recipient = parse_recipient(&transfer.recipient)
+            .unwrap_or_else(|| env::panic_str(BridgeError::InvalidRecipient.as_str()));
+        sub_from_state(
+            OUTSTANDING_PAYOUTS_STORAGE_KEY,
+            transfer.amount.0 - transfer.fee.0,
+        );
+        self.deliver_transfer(
+            TransferToNear {
+                proof_key,
+                sender: transfer.sender,
+                amount: transfer.amount,
+                fee: transfer.fee,
+                recipient: transfer.recipient,
+            },
+            recipient,
+        )
+    }
+
+    /// Vetoes the delayed transfer before its release timestamp. It is moved to the transfers
+    /// waiting for the DAO approval, so it can still be paid out with `approve_queued_transfer`.
+    #[access_control_any(roles(Role::DAO, Role::PauseManager))]
+    pub fn cancel_delayed_transfer(&mut self, proof_key: String) {
+        let key = Self::decode_proof_key(&proof_key);
+        let mut delayed_transfers = Self::delayed_transfers();
+        let delayed_transfer = delayed_transfers
+            .remove(&key)
+            .unwrap_or_else(|| env::panic_str("Delayed transfer not found"));
+        if env::block_timestamp() >= delayed_transfer.release_timestamp.0 {
+            env::panic_str("Delayed transfer can't be cancelled after its release");
+        }
+        write_state(DELAYED_TRANSFERS_STORAGE_KEY, &delayed_transfers);
+
+        let mut queued_transfers = Self::queued_transfers();
+        queued_transfers.insert(&key, &delayed_transfer.transfer);
+        write_state(QUEUED_TRANSFERS_STORAGE_KEY, &queued_transfers);
+
+        Event::DelayedTransferCancelled {
+            proof_key,
+            cancelled_by: env::predecessor_account_id(),
+        }
+        .emit();
+    }
+
+    fn delayed_transfers() -> UnorderedMap<Vec<u8>, DelayedTransfer> {
+        read_state(DELAYED_TRANSFERS_STORAGE_KEY)
+            .unwrap_or_else(|| UnorderedMap::new(DELAYED_TRANSFERS_PREFIX))
+    }
+
+    fn queued_transfers() -> UnorderedMap<Vec<u8>, QueuedTransfer> {
+        read_state(QUEUED_TRANSFERS_STORAGE_KEY)
+            .unwrap_or_else(|| UnorderedMap::new(QUEUED_TRANSFERS_PREFIX))
+    }
+
+    /// Resolves the `ft_transfer_call` of a finalised transfer. Can only be called by the contract
+    /// itself.
+    /// The part of the amount which wasn't used by the receiver is unwrapped and credited to
+    /// `refund_to`, who can get it back with `claim_refund`. Without `refund_to`, e.g. for the
+    /// `target:msg` format, it is recorded as a pending transfer of the receiver instead, which the
+    /// DAO can redirect if the receiver can't take it back. If `near_deposit` failed, the bridge
+    /// still holds the NEAR and the unused amount is handled the same way.
+    pub fn resolve_wnear_transfer_call(
+        &mut self,
+        #[callback_result] used_amount: Result<U128, PromiseError>,
         receiver_id: AccountId,
         amount: U128,
-        memo: Option<String>,
-        msg: String,
-    ) -> PromiseOrValue<U128>;
+        proof_key: String,
+        refund_to: Option<AccountId>,
+    ) -> U128 {
+        near_sdk::assert_self();
 
-    fn ft_transfer(
+        let used_amount = used_amount.map_or(0, |used| std::cmp::min(used.0, amount.0));
+        let unused_amount = amount.0 - used_amount;
+
+        if unused_amount > 0 {
+            self.withdraw_wnear(unused_amount);
+
+            match refund_to {
+                Some(refund_to) => {
+                    let mut refunds = Self::refunds();
+                    let refund = refunds.get(&refund_to).unwrap_or_default() + unused_amount;
+                    refunds.insert(&refund_to, &refund);
+                    add_to_state(OUTSTANDING_PAYOUTS_STORAGE_KEY, unused_amount);
+
+                    Event::RefundCredited {
+                        account_id: refund_to,
+                        amount: U128(unused_amount),
+                        proof_key,
+                    }
+                    .emit();
+                }
+                None => Self::add_pending_transfer(proof_key, receiver_id, U128(unused_amount)),
+            }
+        }
+
+        U128(used_amount)
+    }
+
+    /// Resolves the wNEAR deposit to Aurora. Can only be called by the contract itself.
+    /// The part of the amount which wasn't used by the engine is unwrapped and recorded as a
+    /// pending transfer of the engine account, which the DAO can redirect.
+    pub fn resolve_aurora_deposit(
         &mut self,
-        receiver_id: AccountId,
+        #[callback_result] used_amount: Result<U128, PromiseError>,
+        engine_id: AccountId,
         amount: U128,
-        memo: Option<String>,
-    ) -> PromiseOrValue<U128>;
+        proof_key: String,
+    ) -> U128 {
+        near_sdk::assert_self();
 
-    fn near_deposit(&self);
-    fn storage_deposit(&self, account_id: AccountId);
-}
+        let used_amount = used_amount.map_or(0, |used| std::cmp::min(used.0, amount.0));
+        let unused_amount = amount.0 - used_amount;
 
-#[cfg(not(target_arch = "wasm32"))]
-#[cfg(test)]
-mod tests {
-    use near_sdk::test_utils::test_env::bob;
-    use near_sdk::test_utils::VMContextBuilder;
-    use near_sdk::testing_env;
+        if unused_amount > 0 {
+            self.withdraw_wnear(unused_amount);
+            Self::add_pending_transfer(proof_key, engine_id, U128(unused_amount));
+        }
+
+        U128(used_amount)
+    }
+
+    fn withdraw_wnear(&self, amount: Balance) {
+        let wnear_account_id = self
+            .get_wnear_account_id()
+            .unwrap_or_else(|| env::panic_str("WNear address hasn't been set"));
+        ext_wnear_token::ext(wnear_account_id)
+            .with_static_gas(WNEAR_WITHDRAW_GAS)
+            .with_attached_deposit(ONE_YOCTO)
+            .near_withdraw(U128(amount));
+    }
+
+    /// Returns the amount of NEAR that can be claimed by the account with `claim_refund`.
+    pub fn get_refund(&self, account_id: AccountId) -> U128 {
+        U128(Self::refunds().get(&account_id).unwrap_or_default())
+    }
+
+    /// Transfers all the refunds credited to the predecessor account.
+    #[pause(except(roles(Role::DAO)))]
+    pub fn claim_refund(&mut self) -> Promise {
+        let account_id = env::predecessor_account_id();
+        let amount = Self::refunds()
+            .remove(&account_id)
+            .unwrap_or_else(|| env::panic_str("No refund to claim"));
+        sub_from_state(OUTSTANDING_PAYOUTS_STORAGE_KEY, amount);
+
+        Event::RefundClaimed {
+            account_id: account_id.clone(),
+            amount: U128(amount),
+        }
+        .emit();
+
+        Promise::new(account_id).transfer(amount)
+    }
+
+    fn refunds() -> LookupMap<AccountId, Balance> {
+        LookupMap::new(REFUNDS_STORAGE_KEY)
+    }
+
+    /// Records the transfer as pending for `refund_to`, or the recipient if not set, if the
+    /// native NEAR transfer to the recipient failed. Can only be called by the contract itself.
+    pub fn resolve_native_transfer(
+        &mut self,
+        #[callback_result]
+        #[serializer(borsh)]
+        transfer_result: Result<(), PromiseError>,
+        recipient: AccountId,
+        amount: U128,
+        proof_key: String,
+        refund_to: Option<AccountId>,
+    ) -> bool {
+        near_sdk::assert_self();
+
+        if transfer_result.is_ok() {
+            return true;
+        }
+
+        Self::add_pending_transfer(proof_key, refund_to.unwrap_or(recipient), amount);
+        false
+    }
+
+    fn add_pending_transfer(proof_key: String, recipient: AccountId, amount: U128) {
+        let key = Self::decode_proof_key(&proof_key);
+        let mut pending_transfers = Self::pending_transfers();
+        let replaced = pending_transfers.insert(
+            &key,
+            &PendingTransfer {
+                recipient: recipient.clone(),
+                amount,
+            },
+        );
+        write_state(PENDING_TRANSFERS_STORAGE_KEY, &pending_transfers);
+        if let Some(replaced) = replaced {
+            sub_from_state(OUTSTANDING_PAYOUTS_STORAGE_KEY, replaced.amount.0);
+        }
+        add_to_state(OUTSTANDING_PAYOUTS_STORAGE_KEY, amount.0);
+
+        Event::PendingTransferAdded {
+            proof_key,
+            recipient,
+            amount,
+        }
+        .emit();
+    }
+
+    /// Returns the pending transfer of the event with the given hex encoded proof key.
+    pub fn get_pending_transfer(&self, proof_key: String) -> Option<PendingTransfer> {
+        Self::pending_transfers().get(&Self::decode_proof_key(&proof_key))
+    }
+
+    /// Returns the pending transfers as pairs of hex encoded proof key and transfer.
+    pub fn get_pending_transfers(
+        &self,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<(String, PendingTransfer)> {
+        Self::pending_transfers()
+            .iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .map(|(key, transfer)| (hex::encode(key), transfer))
+            .collect()
+    }
+
+    /// Sends the pending transfer to its recipient again, e.g. after the account was created.
+    /// Can be called by the recipient or the DAO.
+    #[pause(except(roles(Role::DAO)))]
+    pub fn retry_transfer(&mut self, proof_key: String) -> Promise {
+        let transfer = self.take_pending_transfer(&proof_key);
+        Self::send_pending_transfer(proof_key, transfer.recipient, transfer.amount)
+    }
+
+    /// Sends the pending transfer to another account, e.g. if the recipient was misspelled on
+    /// Ethereum. Can be called by the recipient or the DAO.
+    #[pause(except(roles(Role::DAO)))]
+    pub fn redirect_transfer(&mut self, proof_key: String, new_recipient: AccountId) -> Promise {
+        let transfer = self.take_pending_transfer(&proof_key);
+        Self::send_pending_transfer(proof_key, new_recipient, transfer.amount)
+    }
+
+    fn take_pending_transfer(&mut self, proof_key: &str) -> PendingTransfer {
+        let key = Self::decode_proof_key(proof_key);
+        let mut pending_transfers = Self::pending_transfers();
+        let transfer = pending_transfers
+            .get(&key)
+            .unwrap_or_else(|| env::panic_str("Pending transfer not found"));
+
+        let predecessor = env::predecessor_account_id();
+        if predecessor != transfer.recipient && !self.acl_has_role(Role::DAO.into(), predecessor) {
+            env::panic_str("Only the recipient or the DAO can resolve the pending transfer");
+        }
+
+        pending_transfers.remove(&key);
+        write_state(PENDING_TRANSFERS_STORAGE_KEY, &pending_transfers);
+        sub_from_state(OUTSTANDING_PAYOUTS_STORAGE_KEY, transfer.amount.0);
+        transfer
+    }
+
+    fn send_pending_transfer(proof_key: String, recipient: AccountId, amount: U128) -> Promise {
+        Event::PendingTransferRetried {
+            proof_key: proof_key.clone(),
+            recipient: recipient.clone(),
+            amount,
+        }
+        .emit();
+
+        Self::send_native_transfer(recipient, amount.0, proof_key, None)
+    }
+
+    fn send_native_transfer(
+        recipient: AccountId,
+        amount: Balance,
+        proof_key: String,
+        refund_to: Option<AccountId>,
+    ) -> Promise {
+        Promise::new(recipient.clone()).transfer(amount).then(
+            ext_self::ext(env::current_account_id())
+                .with_static_gas(RESOLVE_NATIVE_TRANSFER_GAS)
+                .resolve_native_transfer(recipient, amount.into(), proof_key, refund_to),
+        )
+    }
+
+    fn pending_transfers() -> UnorderedMap<Vec<u8>, PendingTransfer> {
+        read_state(PENDING_TRANSFERS_STORAGE_KEY)
+            .unwrap_or_else(|| UnorderedMap::new(PENDING_TRANSFERS_PREFIX))
+    }
+
+    fn decode_proof_key(proof_key: &str) -> Vec<u8> {
+        hex::decode(proof_key).unwrap_or_else(|_| env::panic_str("Invalid proof key"))
+    }
+
+    /// Returns the fees that would be charged for a transfer of `amount` in the given direction.
+    pub fn estimate_fee(&self, direction: Direction, amount: U128) -> U128 {
+        U128(self.get_fee_config().get(direction).compute(amount.0))
+    }
+
+    pub fn get_fee_config(&self) -> FeeConfig {
+        read_state(FEE_CONFIG_STORAGE_KEY).unwrap_or_default()
+    }
+
+    #[access_control_any(roles(Role::DAO))]
+    pub fn set_fee_config(&mut self, fee_config: FeeConfig) {
+        for fee in [&fee_config.to_ethereum, &fee_config.to_near] {
+            fee.validate().unwrap_or_else(|error| env::panic_str(error));
+        }
+        write_state(FEE_CONFIG_STORAGE_KEY, &fee_config);
+    }
+
+    /// Returns the fees which were charged and not withdrawn yet. They are not a part of the
+    /// available balance.
+    pub fn get_accrued_fees(&self) -> U128 {
+        U128(read_state(ACCRUED_FEES_STORAGE_KEY).unwrap_or_default())
+    }
+
+    /// Transfers `amount` of the accrued fees, all of them if not set, to `receiver_id`.
+    #[access_control_any(roles(Role::DAO))]
+    pub fn withdraw_fees(&mut self, receiver_id: AccountId, amount: Option<U128>) -> Promise {
+        let accrued_fees = self.get_accrued_fees().0;
+        let amount = amount.map_or(accrued_fees, |amount| amount.0);
+        if amount == 0 || amount > accrued_fees {
+            env::panic_str("Invalid amount of fees to withdraw");
+        }
+        write_state(ACCRUED_FEES_STORAGE_KEY, &(accrued_fees - amount));
+
+        Event::FeesWithdrawn {
+            receiver_id: receiver_id.clone(),
+            amount: U128(amount),
+        }
+        .emit();
+
+        Promise::new(receiver_id).transfer(amount)
+    }
+
+    fn accrue_fee(fee: Balance) {
+        let accrued_fees: Balance = read_state(ACCRUED_FEES_STORAGE_KEY).unwrap_or_default();
+        write_state(ACCRUED_FEES_STORAGE_KEY, &(accrued_fees + fee));
+    }
+
+    pub fn get_rate_limits(&self) -> RateLimits {
+        read_state(RATE_LIMITS_STORAGE_KEY).unwrap_or_default()
+    }
+
+    #[access_control_any(roles(Role::DAO))]
+    pub fn set_rate_limits(&mut self, rate_limits: RateLimits) {
+        write_state(RATE_LIMITS_STORAGE_KEY, &rate_limits);
+    }
+
+    /// Returns the amount transferred in the given direction within the rolling 24 hours.
+    pub fn get_daily_volume(&self, direction: Direction) -> U128 {
+        U128(Self::volume_window(direction).volume(env::block_timestamp()))
+    }
+
+    /// Adds the transfer to the daily volume if it doesn't exceed the rate limit.
+    fn record_volume(&self, direction: Direction, amount: Balance) -> Result<(), BridgeError> {
+        self.check_volume(direction, amount)?;
+        Self::add_volume(direction, amount);
+        Ok(())
+    }
+
+    fn check_volume(&self, direction: Direction, amount: Balance) -> Result<(), BridgeError> {
+        let daily_volume = Self::volume_window(direction).volume(env::block_timestamp());
+        self.get_rate_limits()
+            .get(direction)
+            .check(amount, daily_volume)
+    }
+
+    fn add_volume(direction: Direction, amount: Balance) {
+        let mut volume_window = Self::volume_window(direction);
+        volume_window.add(env::block_timestamp(), amount);
+        write_state(Self::volume_storage_key(direction), &volume_window);
+    }
+
+    fn volume_window(direction: Direction) -> VolumeWindow {
+        read_state(Self::volume_storage_key(direction)).unwrap_or_default()
+    }
+
+    fn volume_storage_key(direction: Direction) -> &'static [u8] {
+        match direction {
+            Direction::ToEthereum => VOLUME_TO_ETHEREUM_STORAGE_KEY,
+            Direction::ToNear => VOLUME_TO_NEAR_STORAGE_KEY,
+        }
+    }
+
+    /// Returns the NEAR locked by the migrations to Ethereum, without the fees. It is released by
+    /// the Eth→NEAR transfers and the sweeps to the omni bridge.
+    pub fn get_total_locked(&self) -> U128 {
+        U128(read_state(TOTAL_LOCKED_STORAGE_KEY).unwrap_or_default())
+    }
+
+    /// Sets the locked amount, e.g. to the eNear total supply when the counter is introduced on
+    /// a deployed bridge.
+    #[access_control_any(roles(Role::DAO))]
+    pub fn set_total_locked(&mut self, total_locked: U128) {
+        write_state(TOTAL_LOCKED_STORAGE_KEY, &total_locked.0);
+    }
+
+    pub fn get_balance_reconciliation(&self) -> BalanceReconciliation {
+        let account_balance = env::account_balance();
+        let storage_cost = env::storage_byte_cost() * Balance::from(env::storage_usage());
+        let accrued_fees = self.get_accrued_fees().0;
+        let total_locked = self.get_total_locked().0;
+        let accounted = total_locked
+            .saturating_add(accrued_fees)
+            .saturating_add(storage_cost);
+
+        BalanceReconciliation {
+            account_balance: U128(account_balance),
+            storage_cost: U128(storage_cost),
+            accrued_fees: U128(accrued_fees),
+            total_locked: U128(total_locked),
+            surplus: U128(account_balance.saturating_sub(accounted)),
+            deficit: U128(accounted.saturating_sub(account_balance)),
+        }
+    }
+
+    pub fn get_reserves(&self) -> Reserves {
+        let checkpoints = Self::supply_checkpoints();
+        Reserves {
+            total_migrated: U128(read_state(TOTAL_MIGRATED_STORAGE_KEY).unwrap_or_default()),
+            total_finalised: U128(read_state(TOTAL_FINALISED_STORAGE_KEY).unwrap_or_default()),
+            total_locked: self.get_total_locked(),
+            swept_to_omni_bridge: U128(read_state(SWEPT_LIQUIDITY_STORAGE_KEY).unwrap_or_default()),
+            accrued_fees: self.get_accrued_fees(),
+            latest_supply_checkpoint: Self::last_supply_checkpoint(&checkpoints),
+        }
+    }
+
+    /// Records the eNear `totalSupply` read on Ethereum at `eth_block_number` together with the
+    /// currently locked NEAR. The block numbers of the checkpoints must increase.
+    #[access_control_any(roles(Role::DAO))]
+    pub fn submit_supply_checkpoint(&mut self, total_supply: U128, eth_block_number: u64) {
+        let mut checkpoints = Self::supply_checkpoints();
+        let last_checkpoint = Self::last_supply_checkpoint(&checkpoints);
+        if matches!(last_checkpoint, Some(last) if eth_block_number <= last.eth_block_number) {
+            env::panic_str("Checkpoint block number must be greater than the last one");
+        }
+
+        let checkpoint = SupplyCheckpoint {
+            total_supply,
+            eth_block_number,
+            total_locked: self.get_total_locked(),
+            timestamp: U64(env::block_timestamp()),
+        };
+        checkpoints.push(&checkpoint);
+        write_state(SUPPLY_CHECKPOINTS_STORAGE_KEY, &checkpoints);
+
+        Event::SupplyCheckpointSubmitted(checkpoint).emit();
+    }
+
+    /// Returns the supply checkpoints, oldest first.
+    pub fn get_supply_checkpoints(&self, from_index: u64, limit: u64) -> Vec<SupplyCheckpoint> {
+        Self::supply_checkpoints()
+            .iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .collect()
+    }
+
+    fn supply_checkpoints() -> Vector<SupplyCheckpoint> {
+        read_state(SUPPLY_CHECKPOINTS_STORAGE_KEY)
+            .unwrap_or_else(|| Vector::new(SUPPLY_CHECKPOINTS_PREFIX))
+    }
+
+    fn last_supply_checkpoint(checkpoints: &Vector<SupplyCheckpoint>) -> Option<SupplyCheckpoint> {
+        checkpoints.get(checkpoints.len().checked_sub(1)?)
+    }
+
+    fn add_locked(amount: Balance) {
+        let total_locked: Balance = read_state(TOTAL_LOCKED_STORAGE_KEY).unwrap_or_default();
+        write_state(
+            TOTAL_LOCKED_STORAGE_KEY,
+            &total_locked.saturating_add(amount),
+        );
+    }
+
+    fn sub_locked(amount: Balance) {
+        let total_locked: Balance = read_state(TOTAL_LOCKED_STORAGE_KEY).unwrap_or_default();
+        write_state(
+            TOTAL_LOCKED_STORAGE_KEY,
+            &total_locked.saturating_sub(amount),
+        );
+    }
+
+    /// Returns the NEAR owed to the users which is held by the bridge: the queued and delayed
+    /// transfers, the pending transfers and the refunds.
+    pub fn get_outstanding_payouts(&self) -> U128 {
+        U128(read_state(OUTSTANDING_PAYOUTS_STORAGE_KEY).unwrap_or_default())
+    }
+
+    /// Returns the balance which isn't used for the storage, the accrued fees and the
+    /// outstanding payouts.
+    pub fn get_avialable_balance(&self) -> U128 {
+        U128(
+            env::account_balance()
+                .saturating_sub(env::attached_deposit())
+                .saturating_sub(env::storage_byte_cost() * env::storage_usage() as u128)
+                .saturating_sub(self.get_accrued_fees().0)
+                .saturating_sub(self.get_outstanding_payouts().0),
+        )
+    }
+
+    /// Returns the omni bridges which the Eth→NEAR transfers can be forwarded to.
+    pub fn get_omni_bridges(&self) -> Vec<AccountId> {
+        read_state(OMNI_BRIDGES_STORAGE_KEY).unwrap_or_default()
+    }
+
+    #[access_control_any(roles(Role::DAO))]
+    pub fn add_omni_bridge(&mut self, omni_bridge: AccountId) {
+        let mut omni_bridges = self.get_omni_bridges();
+        if !omni_bridges.contains(&omni_bridge) {
+            omni_bridges.push(omni_bridge);
+            write_state(OMNI_BRIDGES_STORAGE_KEY, &omni_bridges);
+        }
+    }
+
+    #[access_control_any(roles(Role::DAO))]
+    pub fn remove_omni_bridge(&mut self, omni_bridge: AccountId) {
+        let mut omni_bridges = self.get_omni_bridges();
+        omni_bridges.retain(|account_id| account_id != &omni_bridge);
+        write_state(OMNI_BRIDGES_STORAGE_KEY, &omni_bridges);
+    }
+
+    pub fn get_omni_bridge_account_id(&self) -> Option<AccountId> {
+        read_state(OMNI_BRIDGE_ACCOUNT_STORAGE_KEY)
+    }
+
+    /// Sets the omni bridge which receives the liquidity swept with `send_to_omni_bridge`.
+    #[access_control_any(roles(Role::DAO))]
+    pub fn set_omni_bridge_account_id(&mut self, omni_bridge: AccountId) {
+        write_state(OMNI_BRIDGE_ACCOUNT_STORAGE_KEY, &omni_bridge);
+    }
+
+    pub fn get_omni_bridge_liquidity(&self) -> OmniBridgeLiquidity {
+        OmniBridgeLiquidity {
+            swept: U128(read_state(SWEPT_LIQUIDITY_STORAGE_KEY).unwrap_or_default()),
+            locked: self.get_total_locked(),
+        }
+    }
+
+    /// Sends `amount` of the available balance to the omni bridge as wNEAR. Without `amount` all
+    /// of the available balance except `reserve` is sent. At least `reserve` is kept for the
+    /// Eth→NEAR transfers in both cases.
+    #[access_control_any(roles(Role::DAO, Role::LiquiditySweeper))]
+    #[payable]
+    pub fn send_to_omni_bridge(&mut self, amount: Option<U128>, reserve: Option<U128>) -> Promise {
+        let omni_bridge = self
+            .get_omni_bridge_account_id()
+            .unwrap_or_else(|| env::panic_str("Omni bridge address hasn't been set"));
+        let available_balance = self.get_avialable_balance().0;
+        let reserve = reserve.map_or(0, |reserve| reserve.0);
+        let amount = amount.map_or(available_balance.saturating_sub(reserve), |amount| amount.0);
+        if amount == 0 || amount.saturating_add(reserve) > available_balance {
+            env::panic_str("Invalid amount to send to the omni bridge");
+        }
+
+        let wnear_account_id = self
+            .get_wnear_account_id()
+            .unwrap_or_else(|| env::panic_str("WNear address hasn't been set"));
+        // Added back by `resolve_omni_bridge_sweep` if the transfer fails.
+        Self::sub_locked(amount);
+
+        ext_wnear_token::ext(wnear_account_id.clone())
+            .with_static_gas(WNEAR_DEPOSIT_GAS)
+            .with_attached_deposit(amount)
+            .near_deposit()
+            .then(
+                ext_wnear_token::ext(wnear_account_id)
+                    .with_static_gas(FT_TRANSFER_GAS)
+                    .with_attached_deposit(ONE_YOCTO)
+                    .ft_transfer(omni_bridge.clone(), amount.into(), None),
+            )
+            .then(
+                ext_self::ext(env::current_account_id())
+                    .with_static_gas(RESOLVE_OMNI_BRIDGE_SWEEP_GAS)
+                    .resolve_omni_bridge_sweep(omni_bridge, amount.into()),
+            )
+    }
+
+    /// Adds the swept amount to the total if the wNEAR transfer to the omni bridge succeeded,
+    /// otherwise unwraps the wNEAR back and locks it again. Can only be called by the contract
+    /// itself.
+    pub fn resolve_omni_bridge_sweep(
+        &mut self,
+        #[callback_result]
+        #[serializer(borsh)]
+        transfer_result: Result<(), PromiseError>,
+        omni_bridge: AccountId,
+        amount: U128,
+    ) -> bool {
+        near_sdk::assert_self();
+
+        if transfer_result.is_err() {
+            self.withdraw_wnear(amount.0);
+            Self::add_locked(amount.0);
+            return false;
+        }
+
+        add_to_state(SWEPT_LIQUIDITY_STORAGE_KEY, amount.0);
+        Event::LiquiditySwept {
+            omni_bridge,
+            amount,
+        }
+        .emit();
+        true
+    }
+
+    /// Returns the deposit required by `finalise_eth_to_near_transfer` to record the proof. Queued
+    /// and delayed transfers need an additional deposit for their storage. The surplus is
+    /// returned to the caller.
+    pub fn get_proof_deposit(&self) -> U128 {
+        U128(Balance::from(PROOF_STORAGE_USAGE) * env::storage_byte_cost())
+    }
+
+    /// Returns the deposit which is required in addition to the proof deposit if the transfer to
+    /// `recipient` is queued or delayed.
+    pub fn get_queue_deposit(&self, recipient: String) -> U128 {
+        let storage_usage = QUEUED_TRANSFER_STORAGE_USAGE + recipient.len() as StorageUsage;
+        U128(Balance::from(storage_usage) * env::storage_byte_cost())
+    }
+
+    /// Checks whether the provided proof is already used, either under its key or under the
+    /// legacy key of the events finalised before the key was changed.
+    pub fn is_used_proof(&self, #[serializer(borsh)] proof: Proof) -> bool {
+        matches!(proof.get_key(), Ok(key) if self.used_events.contains(&key))
+            || self.used_events.contains(&proof.get_legacy_key())
+    }
+
+    /// Record proof to make sure it is not re-used later for anther deposit.
+    fn record_proof(&mut self, proof_key: &[u8]) -> Balance {
+        near_sdk::assert_self();
+        let initial_storage = env::storage_usage();
+        let proof_key = proof_key.to_vec();
+        assert!(
+            !self.used_events.contains(&proof_key),
+            "{}",
+            BridgeError::ProofAlreadyUsed
+        );
+        self.used_events.insert(&proof_key);
+        let current_storage = env::storage_usage();
+
+        let required_deposit =
+            Balance::from(current_storage - initial_storage) * env::storage_byte_cost();
+
+        required_deposit
+    }
+
+    /// Returns the nonce of the last migration to Ethereum, 0 if there were none.
+    pub fn get_outgoing_nonce(&self) -> u64 {
+        read_state(OUTGOING_NONCE_STORAGE_KEY).unwrap_or_default()
+    }
+
+    #[payable]
+    #[access_control_any(roles(Role::DAO))]
+    pub fn set_wnear_account_id(&mut self, wnear: AccountId) -> Promise {
+        env::storage_write(WNEAR_STORAGE_KEY, &wnear.try_to_vec().unwrap());
+
+        ext_wnear_token::ext(wnear)
+            .with_static_gas(WNEAR_STORAGE_DEPOSIT_GAS)
+            .with_attached_deposit(env::attached_deposit())
+            .storage_deposit(env::current_account_id(), None)
+    }
+
+    pub fn get_wnear_account_id(&self) -> Option<AccountId> {
+        AccountId::try_from_slice(&env::storage_read(WNEAR_STORAGE_KEY)?).ok()
+    }
+
+    #[access_control_any(roles(Role::DAO))]
+    pub fn attach_full_access_key(&self, public_key: PublicKey) -> Promise {
+        Promise::new(env::current_account_id()).add_full_access_key(public_key)
+    }
+
+    pub fn version(&self) -> String {
+        env!("CARGO_PKG_VERSION").to_owned()
+    }
+}
+
+/// Reads a value stored outside of the contract struct under its own storage key.
+fn read_state<T: BorshDeserialize>(key: &[u8]) -> Option<T> {
+    env::storage_read(key).map(|value| T::try_from_slice(&value).unwrap())
+}
+
+fn write_state<T: BorshSerialize>(key: &[u8], value: &T) {
+    env::storage_write(key, &value.try_to_vec().unwrap());
+}
+
+/// Adds `amount` to the total stored under `key`.
+fn add_to_state(key: &[u8], amount: Balance) {
+    let total: Balance = read_state(key).unwrap_or_default();
+    write_state(key, &total.saturating_add(amount));
+}
+
+fn sub_from_state(key: &[u8], amount: Balance) {
+    let total: Balance = read_state(key).unwrap_or_default();
+    write_state(key, &total.saturating_sub(amount));
+}
+
+#[ext_contract(ext_self)]
+pub trait ExtNearBridge {
+    #[result_serializer(borsh)]
+    fn finish_eth_to_near_transfer(
+        &mut self,
+        #[callback_result]
+        #[serializer(borsh)]
+        verification_result: Result<bool, PromiseError>,
+        #[serializer(borsh)] new_owner_id: String,
+        #[serializer(borsh)] amount: Balance,
+        #[serializer(borsh)] sender: String,
+        #[serializer(borsh)] proof_key: Vec<u8>,
+        #[serializer(borsh)] relayer_id: AccountId,
+    ) -> PromiseOrValue<Option<String>>;
+
+    fn report_finalisation(&self, outcomes: Vec<FinalisationOutcome>) -> Vec<FinalisationOutcome>;
+
+    fn resolve_wnear_transfer_call(
+        &mut self,
+        #[callback_result] used_amount: Result<U128, PromiseError>,
+        receiver_id: AccountId,
+        amount: U128,
+        proof_key: String,
+        refund_to: Option<AccountId>,
+    ) -> U128;
+
+    fn resolve_native_transfer(
+        &mut self,
+        #[callback_result]
+        #[serializer(borsh)]
+        transfer_result: Result<(), PromiseError>,
+        recipient: AccountId,
+        amount: U128,
+        proof_key: String,
+        refund_to: Option<AccountId>,
+    ) -> bool;
+
+    fn resolve_aurora_deposit(
+        &mut self,
+        #[callback_result] used_amount: Result<U128, PromiseError>,
+        engine_id: AccountId,
+        amount: U128,
+        proof_key: String,
+    ) -> U128;
+
+    fn send_wnear_transfer(
+        &mut self,
+        #[callback_result] storage_balance: Result<Option<StorageBalance>, PromiseError>,
+        recipient: AccountId,
+        amount: U128,
+        proof_key: String,
+        memo: Option<String>,
+        refund_to: Option<AccountId>,
+    ) -> Promise;
+
+    fn resolve_wnear_transfer(
+        &mut self,
+        #[callback_result]
+        #[serializer(borsh)]
+        transfer_result: Result<(), PromiseError>,
+        recipient: AccountId,
+        amount: U128,
+        proof_key: String,
+        refund_to: Option<AccountId>,
+    ) -> bool;
+
+    fn resolve_omni_bridge_sweep(
+        &mut self,
+        #[callback_result]
+        #[serializer(borsh)]
+        transfer_result: Result<(), PromiseError>,
+        omni_bridge: AccountId,
+        amount: U128,
+    ) -> bool;
+
+    #[result_serializer(borsh)]
+    fn finish_wnear_migration(
+        &mut self,
+        #[callback_result]
+        #[serializer(borsh)]
+        withdraw_result: Result<(), PromiseError>,
+        #[serializer(borsh)] sender_id: AccountId,
+        #[serializer(borsh)] amount: U128,
+        #[serializer(borsh)] recipient: EthAddress,
+    ) -> PromiseOrValue<ResultType>;
+}
+
+#[ext_contract(ext_wnear_token)]
+pub trait ExtWNearToken {
+    fn ft_transfer_call(
+        &self,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<U128>;
+
+    fn ft_transfer(
+        &mut self,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+    ) -> PromiseOrValue<U128>;
+
+    fn near_deposit(&self);
+    fn near_withdraw(&mut self, amount: U128);
+    fn storage_deposit(&self, account_id: AccountId, registration_only: Option<bool>);
+    fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance>;
+}
+
+/// Storage balance of an account on the wNEAR contract.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageBalance {
+    pub total: U128,
+    pub available: U128,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::test_env::bob;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    use super::*;
+    use std::convert::TryInto;
+
+    macro_rules! inner_set_env {
+        ($builder:ident) => {
+            $builder
+        };
+
+        ($builder:ident, $key:ident:$value:expr $(,$key_tail:ident:$value_tail:expr)*) => {
+            {
+               $builder.$key($value.try_into().unwrap());
+               inner_set_env!($builder $(,$key_tail:$value_tail)*)
+            }
+        };
+    }
+
+    macro_rules! set_env {
+        ($($key:ident:$value:expr),* $(,)?) => {
+            let mut builder = VMContextBuilder::new();
+            let mut builder = &mut builder;
+            builder = inner_set_env!(builder, $($key: $value),*);
+            testing_env!(builder.build());
+        };
+    }
+
+    fn alice_near_account() -> AccountId {
+        "alice.near".parse().unwrap()
+    }
+    fn bridge_near_account() -> AccountId {
+        "bridge.near".parse().unwrap()
+    }
+    fn prover_near_account() -> AccountId {
+        "prover".parse().unwrap()
+    }
+    fn relayer_near_account() -> AccountId {
+        "relayer.near".parse().unwrap()
+    }
+    fn wnear_near_account() -> AccountId {
+        "wrap.near".parse().unwrap()
+    }
+    fn e_near_eth_address() -> String {
+        "68a3637ba6e75c0f66b61a42639c4e9fcd3d4824".to_string()
+    }
+    fn sender_eth_address() -> String {
+        "00005474e89094c44da98b954eedeac495271d0f".to_string()
+    }
+    fn alice_eth_address() -> String {
+        "25ac31a08eba29067ba4637788d1dbfb893cebf1".to_string()
+    }
+    fn invalid_eth_address() -> String {
+        "25Ac31A08EBA29067Ba4637788d1DbFB893cEBf".to_string()
+    }
+
+    fn sample_proof() -> Proof {
+        Proof {
+            log_index: 0,
+            log_entry_data: vec![],
+            receipt_index: 0,
+            receipt_data: vec![],
+            header_data: vec![],
+            proof: vec![],
+        }
+    }
+
+    fn create_proof(e_near: String) -> Proof {
+        let event_data = TransferToNearInitiatedEvent {
+            e_near_address: get_eth_address(e_near),
+            sender: sender_eth_address(),
+            amount: 1000,
+            recipient: "123".to_string(),
+        };
+
+        Proof {
+            log_index: 0,
+            log_entry_data: event_data.to_log_entry_data(),
+            receipt_index: 0,
+            receipt_data: vec![],
+            header_data: rlp::encode(&eth_types::BlockHeader::default()).to_vec(),
+            proof: vec![],
+        }
+    }
+
+    #[test]
+    fn can_migrate_near_to_eth_with_valid_params() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        // lets deposit 1 Near
+        let deposit_amount = 1_000_000_000_000_000_000_000_000u128;
+        set_env!(
+            predecessor_account_id: alice_near_account(),
+            attached_deposit: deposit_amount,
+        );
+
+        contract.migrate_to_ethereum(alice_eth_address());
+    }
+
+    #[test]
+    fn migrate_near_to_eth_emits_event_with_increasing_nonce() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        let deposit_amount = 1_000_000_000_000_000_000_000_000u128;
+        set_env!(
+            predecessor_account_id: alice_near_account(),
+            attached_deposit: deposit_amount,
+            block_index: 100,
+        );
+
+        contract.migrate_to_ethereum(alice_eth_address());
+        contract.migrate_to_ethereum(alice_eth_address());
+
+        let expected_event = |nonce| {
+            Event::MigrateToEthereum {
+                sender: alice_near_account(),
+                amount: contract.get_outgoing_transfer(nonce).unwrap().amount,
+                fee: U128(0),
+                recipient: get_eth_address(alice_eth_address()).to_string(),
+                nonce,
+                block_height: 100,
+            }
+            .to_json_string()
+        };
+        assert_eq!(
+            near_sdk::test_utils::get_logs(),
+            vec![
+                format!("EVENT_JSON:{}", expected_event(1)),
+                format!("EVENT_JSON:{}", expected_event(2)),
+            ]
+        );
+        assert_eq!(contract.get_outgoing_nonce(), 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn migrate_near_to_eth_panics_when_attached_deposit_is_zero() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        contract.migrate_to_ethereum(alice_eth_address());
+    }
+
+    #[test]
+    #[should_panic]
+    fn migrate_near_to_eth_panics_when_contract_is_paused() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        contract.pa_pause_feature("migrate_to_ethereum".to_owned());
+
+        // lets deposit 1 Near
+        let deposit_amount = 1_000_000_000_000_000_000_000_000u128;
+        set_env!(
+            predecessor_account_id: bob(),
+            attached_deposit: deposit_amount,
+        );
+
+        contract.migrate_to_ethereum(alice_eth_address());
+    }
+
+    #[test]
+    fn can_migrate_near_to_eth_with_checksummed_address() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        set_env!(
+            predecessor_account_id: alice_near_account(),
+            attached_deposit: 1_000_000_000_000_000_000_000_000u128,
+        );
+
+        let checksummed_address = get_eth_address(alice_eth_address()).to_string();
+        let result = contract.migrate_to_ethereum(checksummed_address);
+        assert_eq!(
+            result,
+            ResultType::MigrateNearToEthereum {
+                amount: contract.get_outgoing_transfer(1).unwrap().amount.0,
+                recipient: get_eth_address(alice_eth_address()),
+            }
+        );
+    }
+
+    #[test]
+    fn migrate_near_to_eth_records_transfer_by_nonce() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        let deposit_amount = 1_000_000_000_000_000_000_000_000u128;
+        set_env!(
+            predecessor_account_id: alice_near_account(),
+            attached_deposit: deposit_amount,
+            block_index: 100,
+        );
+
+        let result = contract.migrate_to_ethereum(alice_eth_address());
+        let transfer = contract.get_outgoing_transfer(1).unwrap();
+        assert_eq!(transfer.sender, alice_near_account());
+        assert_eq!(transfer.recipient, get_eth_address(alice_eth_address()));
+        assert_eq!(transfer.block_height, 100);
+        let storage_cost = contract.get_migration_deposit(alice_near_account()).0;
+        assert_eq!(transfer.amount, U128(deposit_amount - storage_cost));
+        assert_eq!(transfer.fee, U128(0));
+        assert_eq!(
+            result,
+            ResultType::MigrateNearToEthereum {
+                amount: transfer.amount.0,
+                recipient: get_eth_address(alice_eth_address()),
+            }
+        );
+        assert_eq!(contract.get_outgoing_transfer(2), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Attached deposit is not sufficient to cover the storage")]
+    fn migrate_near_to_eth_panics_when_deposit_does_not_cover_storage() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        set_env!(
+            predecessor_account_id: alice_near_account(),
+            attached_deposit: contract.get_migration_deposit(alice_near_account()).0,
+        );
+        contract.migrate_to_ethereum(alice_eth_address());
+    }
+
+    #[test]
+    #[should_panic(expected = "Attached deposit is not sufficient to cover the fee")]
+    fn migrate_near_to_eth_panics_when_deposit_does_not_cover_fee() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+        contract.set_fee_config(sample_fee_config());
+
+        set_env!(
+            predecessor_account_id: alice_near_account(),
+            attached_deposit: contract.get_migration_deposit(alice_near_account()).0 + 100,
+        );
+        contract.migrate_to_ethereum(alice_eth_address());
+    }
+
+    #[test]
+    fn migration_deposit_covers_storage() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        let initial_storage = env::storage_usage();
+        NearBridge::outgoing_transfers().insert(
+            &1,
+            &OutgoingTransfer {
+                sender: alice_near_account(),
+                amount: U128(100),
+                fee: U128(0),
+                recipient: get_eth_address(alice_eth_address()),
+                block_height: 100,
+            },
+        );
+        let storage_cost =
+            Balance::from(env::storage_usage() - initial_storage) * env::storage_byte_cost();
+        assert_eq!(
+            contract.get_migration_deposit(alice_near_account()),
+            U128(storage_cost)
+        );
+    }
+
+    #[test]
+    fn migrate_near_to_eth_returns_nonce_when_enabled() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+        assert!(!contract.get_migration_result_with_nonce());
+        contract.set_migration_result_with_nonce(true);
+        assert!(contract.get_migration_result_with_nonce());
+
+        set_env!(
+            predecessor_account_id: alice_near_account(),
+            attached_deposit: 1_000_000_000_000_000_000_000_000u128,
+        );
+        contract.migrate_to_ethereum(alice_eth_address());
+        let result = contract.migrate_to_ethereum(alice_eth_address());
+
+        let expected = ResultType::MigrateNearToEthereumWithNonce {
+            amount: contract.get_outgoing_transfer(2).unwrap().amount.0,
+            recipient: get_eth_address(alice_eth_address()),
+            nonce: 2,
+        };
+        assert_eq!(result, expected);
+        // Variant index, amount, recipient and nonce.
+        let data = expected.try_to_vec().unwrap();
+        assert_eq!(data.len(), 1 + 16 + 20 + 8);
+        assert_eq!(data[0], 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient permissions for method")]
+    fn set_migration_result_with_nonce_requires_dao() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        set_env!(predecessor_account_id: bridge_near_account());
+        contract.set_migration_result_with_nonce(true);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_ETH_ADDRESS")]
+    fn migrate_near_to_eth_panics_when_eth_address_is_invalid() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        set_env!(
+            predecessor_account_id: alice_near_account(),
+            attached_deposit: 1000,
+        );
+
+        contract.migrate_to_ethereum(invalid_eth_address());
+    }
+
+    #[test]
+    #[should_panic]
+    fn finalise_eth_to_near_transfer_panics_when_contract_is_paused() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        contract.pa_pause_feature("finalise_eth_to_near_transfer".to_owned());
+
+        contract.finalise_eth_to_near_transfer(sample_proof());
+    }
+
+    #[test]
+    #[should_panic(expected = "Pausable: Method is paused")]
+    fn finalise_eth_to_near_transfers_panics_when_finalisation_is_paused() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        contract.pa_pause_feature("finalise_eth_to_near_transfer".to_owned());
+
+        set_env!(predecessor_account_id: relayer_near_account());
+        contract.finalise_eth_to_near_transfers(vec![sample_proof()]);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_WRONG_EVENT_ADDRESS")]
+    fn finalise_eth_to_near_transfer_panics_when_event_originates_from_wrong_contract() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        contract.finalise_eth_to_near_transfer(create_proof(alice_eth_address()));
+    }
+
+    /// Checks that the last event is the failed finalisation with the reason, the proof wasn't
+    /// recorded and the attached deposit was returned to the relayer.
+    fn assert_finalisation_failed(contract: &NearBridge, proof_key: &[u8], reason: &str) {
+        let logs = near_sdk::test_utils::get_logs();
+        let event = logs.last().unwrap();
+        assert!(
+            event.contains(r#""event":"finalisation_failed""#),
+            "{}",
+            event
+        );
+        assert!(
+            event.contains(&format!(r#""reason":"{}""#, reason)),
+            "{}",
+            event
+        );
+        assert!(!contract.used_events.contains(&proof_key.to_vec()));
+        let refund = transfers_to(&relayer_near_account()).last().copied();
+        assert_eq!(
+            refund,
+            Some(env::attached_deposit()).filter(|deposit| *deposit > 0)
+        );
+    }
+
+    #[test]
+    fn finish_eth_to_near_transfer_fails_if_attached_deposit_is_not_sufficient_to_record_proof() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        let attached_deposit = contract.get_proof_deposit().0 - 1;
+        set_env!(
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
+            attached_deposit: attached_deposit,
+        );
+        let proof_key = create_proof(e_near_eth_address()).get_key().unwrap();
+        contract.finish_eth_to_near_transfer(
+            Ok(true),
+            bob().to_string(),
+            10,
+            sender_eth_address(),
+            proof_key.clone(),
+            relayer_near_account(),
+        );
+        assert_finalisation_failed(
+            &contract,
+            &proof_key,
+            "Attached deposit is not sufficient to record proof",
+        );
+    }
+
+    #[test]
+    fn finish_eth_to_near_transfer_emits_event_when_proof_is_not_verified() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        set_env!(
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
+        );
+        let proof = create_proof(e_near_eth_address());
+        contract.finish_eth_to_near_transfer(
+            Ok(false),
+            bob().to_string(),
+            10,
+            sender_eth_address(),
+            proof.get_key().unwrap(),
+            relayer_near_account(),
+        );
+
+        let expected_event = Event::FinalisationFailed {
+            transfer: TransferToNear {
+                proof_key: hex::encode(proof.get_key().unwrap()),
+                sender: sender_eth_address(),
+                amount: U128(10),
+                fee: U128(0),
+                recipient: bob().to_string(),
+            },
+            reason: "Failed to verify the proof".to_owned(),
+        };
+        assert_eq!(
+            near_sdk::test_utils::get_logs(),
+            vec![format!("EVENT_JSON:{}", expected_event.to_json_string())]
+        );
+        assert!(!contract.is_used_proof(proof));
+    }
+
+    #[test]
+    fn finish_eth_to_near_transfer_refunds_relayer_if_proof_is_already_used() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        set_env!(
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
+            attached_deposit: env::storage_byte_cost() * 1000,
+        );
+        let proof_key = create_proof(e_near_eth_address()).get_key().unwrap();
+        let finish = |contract: &mut NearBridge| {
+            contract.finish_eth_to_near_transfer(
+                Ok(true),
+                bob().to_string(),
+                10,
+                sender_eth_address(),
+                proof_key.clone(),
+                relayer_near_account(),
+            )
+        };
+        finish(&mut contract);
+
+        set_env!(
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
+            attached_deposit: env::storage_byte_cost() * 1000,
+        );
+        finish(&mut contract);
+        let logs = near_sdk::test_utils::get_logs();
+        assert_eq!(logs.len(), 1);
+        assert!(logs[0].contains(r#""event":"finalisation_failed""#));
+        assert!(logs[0].contains(r#""reason":"ERR_PROOF_ALREADY_USED""#));
+        assert_eq!(
+            transfers_to(&relayer_near_account()),
+            vec![env::storage_byte_cost() * 1000]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PROOF_ALREADY_USED")]
+    fn finalise_eth_to_near_transfer_rejects_used_proof_before_verification() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        set_env!(
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
+            attached_deposit: env::storage_byte_cost() * 1000,
+        );
+        let proof = create_proof(e_near_eth_address());
+        contract.finish_eth_to_near_transfer(
+            Ok(true),
+            bob().to_string(),
+            10,
+            sender_eth_address(),
+            proof.get_key().unwrap(),
+            relayer_near_account(),
+        );
+
+        set_env!(predecessor_account_id: relayer_near_account());
+        contract.finalise_eth_to_near_transfer(proof);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PROOF_BEING_FINALISED")]
+    fn finalise_eth_to_near_transfer_rejects_proof_being_verified() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        set_env!(
+            predecessor_account_id: relayer_near_account(),
+            block_index: 10,
+        );
+        contract.finalise_eth_to_near_transfer(create_proof(e_near_eth_address()));
+
+        set_env!(
+            predecessor_account_id: bob(),
+            block_index: 10 + PROOF_RESERVATION_BLOCKS - 1,
+        );
+        contract.finalise_eth_to_near_transfer(create_proof(e_near_eth_address()));
+    }
+
+    #[test]
+    fn finalise_eth_to_near_transfer_accepts_proof_after_reservation() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        set_env!(
+            predecessor_account_id: relayer_near_account(),
+            block_index: 10,
+        );
+        let proof = create_proof(e_near_eth_address());
+        contract.finalise_eth_to_near_transfer(proof.clone());
+
+        // Abandoned reservations expire.
+        set_env!(
+            predecessor_account_id: relayer_near_account(),
+            block_index: 10 + PROOF_RESERVATION_BLOCKS,
+        );
+        contract.finalise_eth_to_near_transfer(proof.clone());
+
+        // The callback releases the reservation.
+        set_env!(
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
+            block_index: 10 + PROOF_RESERVATION_BLOCKS,
+        );
+        contract.finish_eth_to_near_transfer(
+            Ok(false),
+            bob().to_string(),
+            10,
+            sender_eth_address(),
+            proof.get_key().unwrap(),
+            relayer_near_account(),
+        );
+
+        set_env!(
+            predecessor_account_id: relayer_near_account(),
+            block_index: 10 + PROOF_RESERVATION_BLOCKS,
+        );
+        contract.finalise_eth_to_near_transfer(proof);
+    }
+
+    #[test]
+    fn finalise_eth_to_near_transfers_skips_rejected_proofs() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        let attached_deposit = env::storage_byte_cost() * 3000;
+        set_env!(
+            predecessor_account_id: relayer_near_account(),
+            current_account_id: bridge_near_account(),
+            attached_deposit: attached_deposit,
+            prepaid_gas: Gas::ONE_TERA * 300,
+        );
+        let proof = create_proof(e_near_eth_address());
+        let other_proof = Proof {
+            log_index: 1,
+            ..create_proof(e_near_eth_address())
+        };
+        let result = contract.finalise_eth_to_near_transfers(vec![
+            proof.clone(),
+            create_proof(alice_eth_address()),
+            other_proof,
+        ]);
+        assert!(matches!(result, PromiseOrValue::Promise(_)));
+        // The promises are scheduled once dropped.
+        drop(result);
+
+        let receipts = near_sdk::test_utils::get_created_receipts();
+        let method_names: Vec<_> = receipts
+            .iter()
+            .flat_map(|receipt| &receipt.actions)
+            .filter_map(|action| match action {
+                near_sdk::mock::VmAction::FunctionCall { function_name, .. } => {
+                    Some(function_name.as_str())
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            method_names,
+            vec![
+                "verify_log_entry",
+                "finish_eth_to_near_transfer",
+                "verify_log_entry",
+                "finish_eth_to_near_transfer",
+                "report_finalisation"
+            ]
+        );
+        // The deposit of the rejected proof is returned.
+        assert_eq!(
+            transfers_to(&relayer_near_account()),
+            vec![attached_deposit / 3]
+        );
+
+        set_env!(
+            predecessor_account_id: relayer_near_account(),
+            prepaid_gas: Gas::ONE_TERA * 300,
+        );
+        let result = contract.finalise_eth_to_near_transfers(vec![proof.clone()]);
+        let rejected = FinalisationOutcome {
+            proof_key: Some(hex::encode(proof.get_key().unwrap())),
+            recorded: false,
+            error: Some("ERR_PROOF_BEING_FINALISED".to_owned()),
+        };
+        assert!(matches!(result, PromiseOrValue::Value(outcomes) if outcomes == vec![rejected]));
+    }
+
+    /// Proofs of the events of one receipt, emitted by several `transferToNear` calls in one
+    /// Ethereum transaction.
+    fn create_receipt_proofs(recipients: &[&str]) -> Vec<Proof> {
+        let log_entries: Vec<_> = recipients
+            .iter()
+            .map(|recipient| {
+                TransferToNearInitiatedEvent {
+                    e_near_address: get_eth_address(e_near_eth_address()),
+                    sender: sender_eth_address(),
+                    amount: 1000,
+                    recipient: recipient.to_string(),
+                }
+                .to_log_entry_data()
+            })
+            .collect();
+        let receipt = eth_types::Receipt {
+            status: true,
+            logs: log_entries
+                .iter()
+                .map(|log_entry| rlp::decode(log_entry).unwrap())
+                .collect(),
+            ..Default::default()
+        };
+        let receipt_data = rlp::encode(&receipt).to_vec();
+
+        log_entries
+            .into_iter()
+            .enumerate()
+            .map(|(log_index, log_entry_data)| Proof {
+                log_index: log_index as u64,
+                log_entry_data,
+                receipt_index: 3,
+                receipt_data: receipt_data.clone(),
+                header_data: rlp::encode(&eth_types::BlockHeader::default()).to_vec(),
+                proof: vec![],
+            })
+            .collect()
+    }
+
+    #[test]
+    fn finish_eth_to_near_transfer_records_events_of_one_receipt() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        let recipients = [
+            alice_near_account().to_string(),
+            bob().to_string(),
+            bob().to_string(),
+        ];
+        let proofs =
+            create_receipt_proofs(&recipients.iter().map(String::as_str).collect::<Vec<_>>());
+        let keys: std::collections::HashSet<_> = proofs
+            .iter()
+            .map(|proof| proof.get_key().unwrap())
+            .collect();
+        assert_eq!(keys.len(), proofs.len());
+
+        for (proof, recipient) in proofs.iter().zip(recipients) {
+            let event =
+                TransferToNearInitiatedEvent::from_log_entry_data(&proof.log_entry_data).unwrap();
+            assert_eq!(event.recipient, recipient);
+
+            set_env!(
+                predecessor_account_id: bridge_near_account(),
+                current_account_id: bridge_near_account(),
+                attached_deposit: env::storage_byte_cost() * 1000,
+            );
+            contract.finish_eth_to_near_transfer(
+                Ok(true),
+                event.recipient,
+                event.amount,
+                event.sender,
+                proof.get_key().unwrap(),
+                relayer_near_account(),
+            );
+        }
+
+        for proof in proofs {
+            assert!(contract.is_used_proof(proof));
+        }
+    }
+
+    #[test]
+    fn finalise_eth_to_near_transfers_accepts_events_of_one_receipt() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        set_env!(
+            predecessor_account_id: relayer_near_account(),
+            current_account_id: bridge_near_account(),
+            prepaid_gas: Gas::ONE_TERA * 300,
+        );
+        let proofs = create_receipt_proofs(&["alice.near", "bob.near"]);
+        let result = contract.finalise_eth_to_near_transfers(proofs);
+        assert!(matches!(result, PromiseOrValue::Promise(_)));
+        drop(result);
+
+        let log_indexes: Vec<_> = near_sdk::test_utils::get_created_receipts()
+            .iter()
+            .flat_map(|receipt| &receipt.actions)
+            .filter_map(|action| match action {
+                near_sdk::mock::VmAction::FunctionCall {
+                    function_name,
+                    args,
+                    ..
+                } if function_name == "verify_log_entry" => {
+                    Some(u64::try_from_slice(&args[..8]).unwrap())
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(log_indexes, vec![0, 1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Not enough gas to finalise the proofs")]
+    fn finalise_eth_to_near_transfers_checks_prepaid_gas() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        set_env!(
+            predecessor_account_id: relayer_near_account(),
+            prepaid_gas: Gas::ONE_TERA * 300,
+        );
+        contract.finalise_eth_to_near_transfers(create_receipt_proofs(&[
+            "alice.near",
+            "bob.near",
+            "alice.near",
+        ]));
+    }
+
+    #[test]
+    #[should_panic(expected = "Not enough gas to finalise the proofs")]
+    fn finalise_eth_to_near_transfers_checks_prepaid_gas_for_delivery() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        set_env!(
+            predecessor_account_id: relayer_near_account(),
+            prepaid_gas: Gas::ONE_TERA * 300,
+        );
+        contract
+            .finalise_eth_to_near_transfers(create_receipt_proofs(&["alice.near", "bob.near:msg"]));
+    }
+
+    fn scheduled_function_calls() -> Vec<(String, Gas)> {
+        near_sdk::test_utils::get_created_receipts()
+            .into_iter()
+            .flat_map(|receipt| receipt.actions)
+            .filter_map(|action| match action {
+                near_sdk::mock::VmAction::FunctionCall {
+                    function_name, gas, ..
+                } => Some((function_name, gas)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn finalise_eth_to_near_transfers_fits_two_proofs_into_300_tgas() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        set_env!(
+            predecessor_account_id: relayer_near_account(),
+            current_account_id: bridge_near_account(),
+            prepaid_gas: Gas::ONE_TERA * 300,
+        );
+        let proofs = create_receipt_proofs(&["alice.near", "bob.near"]);
+        let result = contract.finalise_eth_to_near_transfers(proofs);
+        drop(result);
+
+        let finish_gas = FINISH_FINALISE_OVERHEAD_GAS + RESOLVE_NATIVE_TRANSFER_GAS;
+        assert_eq!(
+            scheduled_function_calls(),
+            vec![
+                ("verify_log_entry".to_owned(), VERIFY_LOG_ENTRY_GAS),
+                ("finish_eth_to_near_transfer".to_owned(), finish_gas),
+                ("verify_log_entry".to_owned(), VERIFY_LOG_ENTRY_GAS),
+                ("finish_eth_to_near_transfer".to_owned(), finish_gas),
+                ("report_finalisation".to_owned(), REPORT_FINALISATION_GAS),
+            ]
+        );
+    }
+
+    #[test]
+    fn finalise_eth_to_near_transfers_attaches_gas_for_delivery() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        set_env!(
+            predecessor_account_id: relayer_near_account(),
+            current_account_id: bridge_near_account(),
+            prepaid_gas: Gas::ONE_TERA * 300,
+        );
+        let proofs = create_receipt_proofs(&["bob.near:msg"]);
+        let result = contract.finalise_eth_to_near_transfers(proofs);
+        drop(result);
+
+        let finish_gas = FINISH_FINALISE_OVERHEAD_GAS
+            + WNEAR_DEPOSIT_GAS
+            + FT_TRANSFER_CALL_GAS
+            + RESOLVE_WNEAR_TRANSFER_CALL_GAS;
+        assert_eq!(
+            scheduled_function_calls(),
+            vec![
+                ("verify_log_entry".to_owned(), VERIFY_LOG_ENTRY_GAS),
+                ("finish_eth_to_near_transfer".to_owned(), finish_gas),
+                ("report_finalisation".to_owned(), REPORT_FINALISATION_GAS),
+            ]
+        );
+    }
+
+    #[test]
+    fn finish_eth_to_near_transfer_fails_without_gas_for_payout() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        set_env!(
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
+            attached_deposit: env::storage_byte_cost() * 1000,
+            prepaid_gas: FINISH_FINALISE_OVERHEAD_GAS,
+        );
+        let proof_key = create_proof(e_near_eth_address()).get_key().unwrap();
+        contract.finish_eth_to_near_transfer(
+            Ok(true),
+            format!("{}:msg", bob()),
+            10,
+            sender_eth_address(),
+            proof_key.clone(),
+            relayer_near_account(),
+        );
+        assert_finalisation_failed(
+            &contract,
+            &proof_key,
+            "Not enough gas to pay out the transfer",
+        );
+    }
+
+    #[test]
+    fn report_finalisation_fills_in_outcomes() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        set_env!(
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
+            attached_deposit: env::storage_byte_cost() * 1000,
+        );
+        let proof_key = |log_index| {
+            Proof {
+                log_index,
+                ..create_proof(e_near_eth_address())
+            }
+            .get_key()
+            .unwrap()
+        };
+        contract.finish_eth_to_near_transfer(
+            Ok(true),
+            bob().to_string(),
+            10,
+            sender_eth_address(),
+            proof_key(0),
+            relayer_near_account(),
+        );
+
+        let outcome = |log_index, recorded, error: Option<&str>| FinalisationOutcome {
+            proof_key: Some(hex::encode(proof_key(log_index))),
+            recorded,
+            error: error.map(str::to_owned),
+        };
+        let rejected = outcome(3, false, Some("ERR_PROOF_ALREADY_USED"));
+
+        testing_env!(
+            VMContextBuilder::new()
+                .predecessor_account_id(bridge_near_account())
+                .current_account_id(bridge_near_account())
+                .build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![
+                PromiseResult::Successful(b"null".to_vec()),
+                PromiseResult::Successful(b"\"Not enough gas to pay out the transfer\"".to_vec()),
+                PromiseResult::Failed,
+            ],
+        );
+        assert_eq!(
+            contract.report_finalisation(vec![
+                outcome(0, false, None),
+                rejected.clone(),
+                outcome(1, false, None),
+                outcome(2, false, None),
+            ]),
+            vec![
+                outcome(0, true, None),
+                rejected,
+                outcome(1, false, Some("Not enough gas to pay out the transfer")),
+                outcome(2, false, Some("Failed to finalise the transfer")),
+            ]
+        );
+    }
+
+    fn transfers_to(account_id: &AccountId) -> Vec<Balance> {
+        near_sdk::test_utils::get_created_receipts()
+            .iter()
+            .filter(|receipt| &receipt.receiver_id == account_id)
+            .flat_map(|receipt| &receipt.actions)
+            .filter_map(|action| match action {
+                near_sdk::mock::VmAction::Transfer { deposit } => Some(*deposit),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn finish_eth_to_near_transfer_refunds_deposit_surplus() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        let attached_deposit = env::storage_byte_cost() * 1000;
+        set_env!(
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
+            attached_deposit: attached_deposit,
+        );
+        contract.finish_eth_to_near_transfer(
+            Ok(true),
+            bob().to_string(),
+            10,
+            sender_eth_address(),
+            create_proof(e_near_eth_address()).get_key().unwrap(),
+            relayer_near_account(),
+        );
+
+        assert_eq!(
+            transfers_to(&relayer_near_account()),
+            vec![attached_deposit - contract.get_proof_deposit().0]
+        );
+    }
+
+    #[test]
+    fn finish_eth_to_near_transfer_refunds_deposit_when_proof_is_not_verified() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        let attached_deposit = env::storage_byte_cost() * 1000;
+        set_env!(
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
+            attached_deposit: attached_deposit,
+        );
+        contract.finish_eth_to_near_transfer(
+            Ok(false),
+            bob().to_string(),
+            10,
+            sender_eth_address(),
+            create_proof(e_near_eth_address()).get_key().unwrap(),
+            relayer_near_account(),
+        );
+
+        assert_eq!(
+            transfers_to(&relayer_near_account()),
+            vec![attached_deposit]
+        );
+    }
+
+    #[test]
+    fn proof_deposit_covers_recorded_proof() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        set_env!(
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
+        );
+        let required_deposit =
+            contract.record_proof(&create_proof(e_near_eth_address()).get_key().unwrap());
+        assert_eq!(contract.get_proof_deposit(), U128(required_deposit));
+    }
+
+    #[test]
+    fn finish_eth_to_near_transfer_emits_finalised_events() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        set_env!(
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
+            attached_deposit: env::storage_byte_cost() * 1000,
+        );
+        let mut proof = create_proof(e_near_eth_address());
+        contract.finish_eth_to_near_transfer(
+            Ok(true),
+            bob().to_string(),
+            10,
+            sender_eth_address(),
+            proof.get_key().unwrap(),
+            relayer_near_account(),
+        );
+        let transfer = TransferToNear {
+            proof_key: hex::encode(proof.get_key().unwrap()),
+            sender: sender_eth_address(),
+            amount: U128(10),
+            fee: U128(0),
+            recipient: bob().to_string(),
+        };
+        assert_eq!(
+            near_sdk::test_utils::get_logs(),
+            vec![format!(
+                "EVENT_JSON:{}",
+                Event::Finalised(transfer).to_json_string()
+            )]
+        );
+
+        proof.log_index = 1;
+        let recipient = format!("{}:msg", bob());
+        contract.finish_eth_to_near_transfer(
+            Ok(true),
+            recipient.clone(),
+            10,
+            sender_eth_address(),
+            proof.get_key().unwrap(),
+            relayer_near_account(),
+        );
+        let transfer = TransferToNear {
+            proof_key: hex::encode(proof.get_key().unwrap()),
+            sender: sender_eth_address(),
+            amount: U128(10),
+            fee: U128(0),
+            recipient,
+        };
+        assert_eq!(
+            near_sdk::test_utils::get_logs().last().unwrap(),
+            &format!(
+                "EVENT_JSON:{}",
+                Event::FinalisedViaWnear(transfer).to_json_string()
+            )
+        );
+    }
+
+    #[test]
+    fn resolve_wnear_transfer_call_credits_unused_amount() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        set_env!(
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
+        );
+        let used = contract.resolve_wnear_transfer_call(
+            Ok(U128(70)),
+            bob(),
+            U128(100),
+            "abcd".to_owned(),
+            Some(alice_near_account()),
+        );
+        assert_eq!(used, U128(70));
+        assert_eq!(contract.get_refund(alice_near_account()), U128(30));
+
+        let used = contract.resolve_wnear_transfer_call(
+            Err(PromiseError::Failed),
+            bob(),
+            U128(100),
+            "abcd".to_owned(),
+            Some(alice_near_account()),
+        );
+        assert_eq!(used, U128(0));
+        assert_eq!(contract.get_refund(alice_near_account()), U128(130));
+
+        let used = contract.resolve_wnear_transfer_call(
+            Ok(U128(100)),
+            bob(),
+            U128(100),
+            "abcd".to_owned(),
+            Some(bob()),
+        );
+        assert_eq!(used, U128(100));
+        assert_eq!(contract.get_refund(bob()), U128(0));
+    }
+
+    #[test]
+    fn resolve_wnear_transfer_call_records_unused_amount_as_pending_without_refund_to() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        set_env!(
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
+        );
+        let used = contract.resolve_wnear_transfer_call(
+            Ok(U128(70)),
+            bob(),
+            U128(100),
+            "abcd".to_owned(),
+            None,
+        );
+        assert_eq!(used, U128(70));
+        assert_eq!(contract.get_refund(bob()), U128(0));
+        assert_eq!(
+            contract.get_pending_transfer("abcd".to_owned()),
+            Some(PendingTransfer {
+                recipient: bob(),
+                amount: U128(30),
+            })
+        );
+
+        // The DAO can send it elsewhere if the receiver can't take it back.
+        set_env!(
+            predecessor_account_id: alice_near_account(),
+            current_account_id: bridge_near_account(),
+        );
+        contract.redirect_transfer("abcd".to_owned(), alice_near_account());
+        assert_eq!(contract.get_pending_transfer("abcd".to_owned()), None);
+    }
+
+    #[test]
+    fn claim_refund_transfers_credited_refund() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        set_env!(
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
+        );
+        contract.resolve_wnear_transfer_call(
+            Ok(U128(0)),
+            bob(),
+            U128(100),
+            "abcd".to_owned(),
+            Some(bob()),
+        );
+
+        set_env!(
+            predecessor_account_id: bob(),
+            current_account_id: bridge_near_account(),
+        );
+        contract.claim_refund();
+
+        assert_eq!(contract.get_refund(bob()), U128(0));
+        assert_eq!(
+            near_sdk::test_utils::get_logs(),
+            vec![format!(
+                "EVENT_JSON:{}",
+                Event::RefundClaimed {
+                    account_id: bob(),
+                    amount: U128(100),
+                }
+                .to_json_string()
+            )]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "No refund to claim")]
+    fn claim_refund_panics_without_refund() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        contract.claim_refund();
+    }
+
+    #[test]
+    fn resolve_native_transfer_records_failed_transfer_as_pending() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        set_env!(
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
+        );
+        assert!(contract.resolve_native_transfer(Ok(()), bob(), U128(100), "ab".to_owned(), None));
+        assert!(contract.get_pending_transfer("ab".to_owned()).is_none());
+
+        assert!(!contract.resolve_native_transfer(
+            Err(PromiseError::Failed),
+            bob(),
+            U128(100),
+            "cd".to_owned(),
+            None
+        ));
+        let pending_transfer = PendingTransfer {
+            recipient: bob(),
+            amount: U128(100),
+        };
+        assert_eq!(
+            contract.get_pending_transfer("cd".to_owned()),
+            Some(pending_transfer)
+        );
+        assert_eq!(
+            contract.get_pending_transfers(0, 10),
+            vec![(
+                "cd".to_owned(),
+                PendingTransfer {
+                    recipient: bob(),
+                    amount: U128(100),
+                }
+            )]
+        );
+
+        set_env!(
+            predecessor_account_id: bob(),
+            current_account_id: bridge_near_account(),
+        );
+        contract.retry_transfer("cd".to_owned());
+        assert!(contract.get_pending_transfer("cd".to_owned()).is_none());
+    }
+
+    #[test]
+    fn dao_can_redirect_pending_transfer() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        set_env!(
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
+        );
+        contract.resolve_native_transfer(
+            Err(PromiseError::Failed),
+            "bobb.near".parse().unwrap(),
+            U128(100),
+            "cd".to_owned(),
+            None,
+        );
+
+        set_env!(
+            predecessor_account_id: alice_near_account(),
+            current_account_id: bridge_near_account(),
+        );
+        contract.redirect_transfer("cd".to_owned(), bob());
+        assert!(contract.get_pending_transfer("cd".to_owned()).is_none());
+        assert_eq!(
+            near_sdk::test_utils::get_logs(),
+            vec![format!(
+                "EVENT_JSON:{}",
+                Event::PendingTransferRetried {
+                    proof_key: "cd".to_owned(),
+                    recipient: bob(),
+                    amount: U128(100),
+                }
+                .to_json_string()
+            )]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the recipient or the DAO can resolve the pending transfer")]
+    fn redirect_transfer_panics_for_unauthorized_account() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        set_env!(
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
+        );
+        contract.resolve_native_transfer(
+            Err(PromiseError::Failed),
+            "bobb.near".parse().unwrap(),
+            U128(100),
+            "cd".to_owned(),
+            None,
+        );
+
+        set_env!(
+            predecessor_account_id: bob(),
+            current_account_id: bridge_near_account(),
+        );
+        contract.redirect_transfer("cd".to_owned(), bob());
+    }
+
+    #[test]
+    fn finalise_eth_to_near_transfer_works_with_valid_params() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        // Alice deposit 1 Near to migrate to eth
+        let deposit_amount = 1_000_000_000_000_000_000_000_000u128;
+        set_env!(
+            predecessor_account_id: alice_near_account(),
+            attached_deposit: deposit_amount,
+        );
+
+        contract.migrate_to_ethereum(alice_eth_address());
+
+        // todo adjust attached deposit down
+
+        // Lets suppose Alice migrates back
+        contract.finalise_eth_to_near_transfer(create_proof(e_near_eth_address()));
+
+        // todo asserts i.e. that alice has received the 1 near back etc.
+    }
+
+    #[test]
+    fn test_set_wnear_account_id() {
+        set_env!(
+            predecessor_account_id: alice_near_account(),
+            signer_account_id: alice_near_account()
+        );
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            "old_wnear_near.near".parse().unwrap(),
+        );
+
+        contract.set_wnear_account_id(wnear_near_account());
+
+        assert_eq!(
+            contract.get_wnear_account_id().unwrap(),
+            wnear_near_account()
+        );
+    }
+
+    fn sample_fee_config() -> FeeConfig {
+        FeeConfig {
+            to_ethereum: Fee {
+                fixed: U128(100),
+                bps: 10,
+                min: U128(0),
+                max: None,
+            },
+            to_near: Fee {
+                fixed: U128(0),
+                bps: 0,
+                min: U128(3),
+                max: Some(U128(5)),
+            },
+        }
+    }
+
+    #[test]
+    fn migrate_near_to_eth_charges_fee() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+        contract.set_fee_config(sample_fee_config());
+        assert_eq!(contract.get_fee_config(), sample_fee_config());
+
+        let deposit_amount = 1_000_000_000_000_000_000_000_000u128;
+        set_env!(
+            predecessor_account_id: alice_near_account(),
+            attached_deposit: deposit_amount,
+        );
+        contract.migrate_to_ethereum(alice_eth_address());
+
+        let transfer = contract.get_outgoing_transfer(1).unwrap();
+        let amount = deposit_amount - contract.get_migration_deposit(alice_near_account()).0;
+        let expected_fee = contract.estimate_fee(Direction::ToEthereum, U128(amount));
+        assert_eq!(transfer.amount.0 + transfer.fee.0, amount);
+        assert_eq!(transfer.fee, expected_fee);
+        assert_eq!(contract.get_accrued_fees(), transfer.fee);
+    }
+
+    #[test]
+    fn finish_eth_to_near_transfer_charges_fee() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+        contract.set_fee_config(sample_fee_config());
+        assert_eq!(contract.estimate_fee(Direction::ToNear, U128(10)), U128(3));
+
+        set_env!(
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
+            attached_deposit: env::storage_byte_cost() * 1000,
+        );
+        let proof = create_proof(e_near_eth_address());
+        contract.finish_eth_to_near_transfer(
+            Ok(true),
+            bob().to_string(),
+            10,
+            sender_eth_address(),
+            proof.get_key().unwrap(),
+            relayer_near_account(),
+        );
+
+        let transfer = TransferToNear {
+            proof_key: hex::encode(proof.get_key().unwrap()),
+            sender: sender_eth_address(),
+            amount: U128(10),
+            fee: U128(3),
+            recipient: bob().to_string(),
+        };
+        assert_eq!(
+            near_sdk::test_utils::get_logs(),
+            vec![format!(
+                "EVENT_JSON:{}",
+                Event::Finalised(transfer).to_json_string()
+            )]
+        );
+        assert_eq!(contract.get_accrued_fees(), U128(3));
+    }
+
+    #[test]
+    fn transfers_update_total_locked() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+        contract.set_fee_config(sample_fee_config());
+
+        let deposit_amount = 1_000_000_000_000_000_000_000_000u128;
+        set_env!(
+            predecessor_account_id: alice_near_account(),
+            attached_deposit: deposit_amount,
+        );
+        contract.migrate_to_ethereum(alice_eth_address());
+        let locked = contract.get_outgoing_transfer(1).unwrap().amount.0;
+        assert_eq!(contract.get_total_locked(), U128(locked));
+
+        set_env!(
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
+            attached_deposit: env::storage_byte_cost() * 1000,
+        );
+        contract.finish_eth_to_near_transfer(
+            Ok(true),
+            bob().to_string(),
+            10,
+            sender_eth_address(),
+            create_proof(e_near_eth_address()).get_key().unwrap(),
+            relayer_near_account(),
+        );
+        assert_eq!(contract.get_total_locked(), U128(locked - 10));
+
+        let reserves = contract.get_reserves();
+        assert_eq!(reserves.total_migrated, U128(locked));
+        assert_eq!(reserves.total_finalised, U128(10));
+        assert_eq!(reserves.total_locked, U128(locked - 10));
+        assert_eq!(reserves.swept_to_omni_bridge, U128(0));
+        assert_eq!(reserves.accrued_fees, contract.get_accrued_fees());
+        assert_eq!(reserves.latest_supply_checkpoint, None);
+    }
+
+    #[test]
+    fn test_submit_supply_checkpoint() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+        contract.set_total_locked(U128(100));
+        contract.submit_supply_checkpoint(U128(90), 10);
+
+        set_env!(
+            predecessor_account_id: alice_near_account(),
+            block_timestamp: 5,
+        );
+        contract.submit_supply_checkpoint(U128(95), 11);
+
+        let checkpoint = SupplyCheckpoint {
+            total_supply: U128(95),
+            eth_block_number: 11,
+            total_locked: U128(100),
+            timestamp: U64(5),
+        };
+        assert_eq!(
+            contract.get_reserves().latest_supply_checkpoint,
+            Some(checkpoint.clone())
+        );
+        assert_eq!(
+            contract.get_supply_checkpoints(1, 10),
+            vec![checkpoint.clone()]
+        );
+        assert_eq!(contract.get_supply_checkpoints(0, 10).len(), 2);
+        assert_eq!(
+            near_sdk::test_utils::get_logs(),
+            vec![format!(
+                "EVENT_JSON:{}",
+                Event::SupplyCheckpointSubmitted(checkpoint).to_json_string()
+            )]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Checkpoint block number must be greater than the last one")]
+    fn submit_supply_checkpoint_panics_on_old_block() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+        contract.submit_supply_checkpoint(U128(90), 10);
+        contract.submit_supply_checkpoint(U128(90), 10);
+    }
+
+    #[test]
+    fn test_balance_reconciliation() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        let reconciliation = contract.get_balance_reconciliation();
+        assert_eq!(reconciliation.total_locked, U128(0));
+        assert_eq!(reconciliation.deficit, U128(0));
+        assert_eq!(
+            reconciliation.surplus.0,
+            reconciliation.account_balance.0 - reconciliation.storage_cost.0
+        );
+
+        let total_locked = env::account_balance() + 10;
+        contract.set_total_locked(U128(total_locked));
+        let reconciliation = contract.get_balance_reconciliation();
+        assert_eq!(reconciliation.surplus, U128(0));
+        assert_eq!(
+            reconciliation.deficit.0,
+            total_locked + reconciliation.storage_cost.0 - reconciliation.account_balance.0
+        );
+    }
+
+    #[test]
+    fn finish_eth_to_near_transfer_fails_when_amount_does_not_cover_fee() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+        contract.set_fee_config(sample_fee_config());
+
+        set_env!(
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
+            attached_deposit: env::storage_byte_cost() * 1000,
+        );
+        let proof_key = create_proof(e_near_eth_address()).get_key().unwrap();
+        contract.finish_eth_to_near_transfer(
+            Ok(true),
+            bob().to_string(),
+            3,
+            sender_eth_address(),
+            proof_key.clone(),
+            relayer_near_account(),
+        );
+        assert_finalisation_failed(
+            &contract,
+            &proof_key,
+            "Transferred amount is not sufficient to cover the fee",
+        );
+        assert_eq!(contract.get_accrued_fees(), U128(0));
+    }
+
+    #[test]
+    fn dao_can_withdraw_fees() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+        NearBridge::accrue_fee(100);
+        NearBridge::accrue_fee(50);
+        assert_eq!(contract.get_accrued_fees(), U128(150));
+
+        contract.withdraw_fees(bob(), Some(U128(100)));
+        assert_eq!(contract.get_accrued_fees(), U128(50));
+        assert_eq!(
+            near_sdk::test_utils::get_logs(),
+            vec![format!(
+                "EVENT_JSON:{}",
+                Event::FeesWithdrawn {
+                    receiver_id: bob(),
+                    amount: U128(100),
+                }
+                .to_json_string()
+            )]
+        );
+
+        contract.withdraw_fees(bob(), None);
+        assert_eq!(contract.get_accrued_fees(), U128(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid amount of fees to withdraw")]
+    fn withdraw_fees_panics_when_amount_exceeds_accrued_fees() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+        NearBridge::accrue_fee(100);
+        contract.withdraw_fees(bob(), Some(U128(101)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Fee basis points must not exceed 10000")]
+    fn set_fee_config_panics_when_fee_is_invalid() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+        let mut fee_config = sample_fee_config();
+        fee_config.to_near.bps = 10_001;
+        contract.set_fee_config(fee_config);
+    }
+
+    fn sample_rate_limits(queue_exceeding_transfers: bool) -> RateLimits {
+        let limit = RateLimit {
+            max_transfer: Some(U128(100)),
+            max_daily_volume: Some(U128(150)),
+        };
+        RateLimits {
+            to_ethereum: limit.clone(),
+            to_near: limit,
+            queue_exceeding_transfers,
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_TRANSFER_LIMIT_EXCEEDED")]
+    fn migrate_near_to_eth_panics_when_transfer_limit_is_exceeded() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+        contract.set_rate_limits(sample_rate_limits(true));
+
+        set_env!(
+            predecessor_account_id: alice_near_account(),
+            attached_deposit: 1_000_000_000_000_000_000_000_000u128,
+        );
+        contract.migrate_to_ethereum(alice_eth_address());
+    }
+
+    #[test]
+    fn finish_eth_to_near_transfer_tracks_daily_volume() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+        contract.set_rate_limits(sample_rate_limits(false));
+
+        set_env!(
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
+            attached_deposit: env::storage_byte_cost() * 1000,
+            block_timestamp: 1_000_000_000u64,
+        );
+        let mut proof = create_proof(e_near_eth_address());
+        for log_index in 0..2 {
+            proof.log_index = log_index;
+            contract.finish_eth_to_near_transfer(
+                Ok(true),
+                bob().to_string(),
+                60,
+                sender_eth_address(),
+                proof.get_key().unwrap(),
+                relayer_near_account(),
+            );
+        }
+        assert_eq!(contract.get_daily_volume(Direction::ToNear), U128(120));
+        assert_eq!(contract.get_daily_volume(Direction::ToEthereum), U128(0));
+
+        set_env!(
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
+            block_timestamp: 1_000_000_000u64 + 24 * 60 * 60 * 1_000_000_000,
+        );
+        assert_eq!(contract.get_daily_volume(Direction::ToNear), U128(0));
+    }
+
+    #[test]
+    fn finish_eth_to_near_transfer_fails_when_daily_volume_is_exceeded() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+        contract.set_rate_limits(sample_rate_limits(false));
+
+        set_env!(
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
+            attached_deposit: env::storage_byte_cost() * 1000,
+        );
+        let mut proof = create_proof(e_near_eth_address());
+        for log_index in 0..3 {
+            proof.log_index = log_index;
+            contract.finish_eth_to_near_transfer(
+                Ok(true),
+                bob().to_string(),
+                60,
+                sender_eth_address(),
+                proof.get_key().unwrap(),
+                relayer_near_account(),
+            );
+        }
+        assert_finalisation_failed(
+            &contract,
+            &proof.get_key().unwrap(),
+            "ERR_DAILY_VOLUME_LIMIT_EXCEEDED",
+        );
+        assert_eq!(contract.get_daily_volume(Direction::ToNear), U128(120));
+    }
+
+    #[test]
+    fn finish_eth_to_near_transfer_queues_transfer_exceeding_limit() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+        contract.set_rate_limits(sample_rate_limits(true));
+
+        set_env!(
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
+            attached_deposit: env::storage_byte_cost() * 1000,
+        );
+        let proof = create_proof(e_near_eth_address());
+        let proof_key = hex::encode(proof.get_key().unwrap());
+        contract.finish_eth_to_near_transfer(
+            Ok(true),
+            bob().to_string(),
+            1000,
+            sender_eth_address(),
+            proof.get_key().unwrap(),
+            relayer_near_account(),
+        );
+
+        let transfer = TransferToNear {
+            proof_key: proof_key.clone(),
+            sender: sender_eth_address(),
+            amount: U128(1000),
+            fee: U128(0),
+            recipient: bob().to_string(),
+        };
+        assert_eq!(
+            near_sdk::test_utils::get_logs(),
+            vec![format!(
+                "EVENT_JSON:{}",
+                Event::TransferQueued(transfer.clone()).to_json_string()
+            )]
+        );
+        assert!(contract.is_used_proof(proof));
+        let queued_transfer = QueuedTransfer {
+            sender: sender_eth_address(),
+            recipient: bob().to_string(),
+            amount: U128(1000),
+            fee: U128(0),
+            volume_recorded: false,
+        };
+        assert_eq!(
+            contract.get_queued_transfers(0, 10),
+            vec![(proof_key.clone(), queued_transfer)]
+        );
+        assert_eq!(contract.get_daily_volume(Direction::ToNear), U128(0));
+
+        set_env!(
+            predecessor_account_id: alice_near_account(),
+            current_account_id: bridge_near_account(),
+        );
+        contract.approve_queued_transfer(proof_key.clone());
+        assert_eq!(contract.get_queued_transfer(proof_key), None);
+        assert_eq!(contract.get_daily_volume(Direction::ToNear), U128(1000));
+        assert_eq!(
+            near_sdk::test_utils::get_logs(),
+            vec![format!(
+                "EVENT_JSON:{}",
+                Event::Finalised(transfer).to_json_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn finish_eth_to_near_transfer_fails_when_deposit_does_not_cover_queue() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+        contract.set_rate_limits(sample_rate_limits(true));
+
+        let attached_deposit =
+            contract.get_proof_deposit().0 + contract.get_queue_deposit(bob().to_string()).0 - 1;
+        set_env!(
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
+            attached_deposit: attached_deposit,
+        );
+        let proof_key = create_proof(e_near_eth_address()).get_key().unwrap();
+        contract.finish_eth_to_near_transfer(
+            Ok(true),
+            bob().to_string(),
+            1000,
+            sender_eth_address(),
+            proof_key.clone(),
+            relayer_near_account(),
+        );
+        assert_finalisation_failed(
+            &contract,
+            &proof_key,
+            "Attached deposit is not sufficient to queue the transfer",
+        );
+        assert!(contract.get_queued_transfers(0, 10).is_empty());
+    }
+
+    #[test]
+    fn queue_deposit_covers_delayed_transfer() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        let mut delayed_transfers = NearBridge::delayed_transfers();
+        let initial_storage = env::storage_usage();
+        delayed_transfers.insert(
+            &create_proof(e_near_eth_address()).get_key().unwrap(),
+            &DelayedTransfer {
+                transfer: QueuedTransfer {
+                    sender: sender_eth_address(),
+                    recipient: bob().to_string(),
+                    amount: U128(100),
+                    fee: U128(0),
+                    volume_recorded: true,
+                },
+                release_timestamp: U64(0),
+            },
+        );
+        let storage_cost =
+            Balance::from(env::storage_usage() - initial_storage) * env::storage_byte_cost();
+        assert_eq!(
+            contract.get_queue_deposit(bob().to_string()),
+            U128(storage_cost)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient permissions for method")]
+    fn approve_queued_transfer_requires_dao() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        set_env!(predecessor_account_id: bob());
+        contract.approve_queued_transfer("cd".to_owned());
+    }
+
+    fn finish_delayed_transfer(contract: &mut NearBridge, amount: Balance) -> String {
+        contract.set_large_transfer_delay(Some(LargeTransferDelay {
+            threshold: U128(100),
+            delay_seconds: 60,
+        }));
+
+        set_env!(
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
+            attached_deposit: env::storage_byte_cost() * 1000,
+            block_timestamp: NANOSECONDS_IN_SECOND,
+        );
+        let proof = create_proof(e_near_eth_address());
+        contract.finish_eth_to_near_transfer(
+            Ok(true),
+            bob().to_string(),
+            amount,
+            sender_eth_address(),
+            proof.get_key().unwrap(),
+            relayer_near_account(),
+        );
+        hex::encode(proof.get_key().unwrap())
+    }
+
+    #[test]
+    fn finish_eth_to_near_transfer_delays_large_transfer() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+        let proof_key = finish_delayed_transfer(&mut contract, 100);
+
+        let transfer = TransferToNear {
+            proof_key: proof_key.clone(),
+            sender: sender_eth_address(),
+            amount: U128(100),
+            fee: U128(0),
+            recipient: bob().to_string(),
+        };
+        assert_eq!(
+            near_sdk::test_utils::get_logs(),
+            vec![format!(
+                "EVENT_JSON:{}",
+                Event::TransferDelayed {
+                    transfer: transfer.clone(),
+                    release_timestamp: U64(61 * NANOSECONDS_IN_SECOND),
+                }
+                .to_json_string()
+            )]
+        );
+        assert_eq!(
+            contract.get_delayed_transfers(0, 10),
+            vec![(
+                proof_key.clone(),
+                DelayedTransfer {
+                    transfer: QueuedTransfer {
+                        sender: sender_eth_address(),
+                        recipient: bob().to_string(),
+                        amount: U128(100),
+                        fee: U128(0),
+                        volume_recorded: true,
+                    },
+                    release_timestamp: U64(61 * NANOSECONDS_IN_SECOND),
+                }
+            )]
+        );
+
+        set_env!(
+            predecessor_account_id: alice_near_account(),
+            current_account_id: bridge_near_account(),
+            block_timestamp: 61 * NANOSECONDS_IN_SECOND,
+        );
+        contract.release_delayed_transfer(proof_key.clone());
+        assert_eq!(contract.get_delayed_transfer(proof_key), None);
+        assert_eq!(
+            near_sdk::test_utils::get_logs(),
+            vec![format!(
+                "EVENT_JSON:{}",
+                Event::Finalised(transfer).to_json_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn finish_eth_to_near_transfer_does_not_delay_small_transfer() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+        let proof_key = finish_delayed_transfer(&mut contract, 99);
+
+        assert_eq!(contract.get_delayed_transfer(proof_key), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Delayed transfer can't be released yet")]
+    fn release_delayed_transfer_panics_before_release_timestamp() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+        let proof_key = finish_delayed_transfer(&mut contract, 100);
+
+        set_env!(
+            predecessor_account_id: alice_near_account(),
+            current_account_id: bridge_near_account(),
+            block_timestamp: 60 * NANOSECONDS_IN_SECOND,
+        );
+        contract.release_delayed_transfer(proof_key);
+    }
+
+    #[test]
+    fn pause_manager_can_cancel_delayed_transfer() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+        contract.acl_grant_role(Role::PauseManager.into(), bob());
+        let proof_key = finish_delayed_transfer(&mut contract, 100);
+
+        set_env!(
+            predecessor_account_id: bob(),
+            current_account_id: bridge_near_account(),
+        );
+        contract.cancel_delayed_transfer(proof_key.clone());
+
+        assert_eq!(contract.get_delayed_transfer(proof_key.clone()), None);
+        assert_eq!(
+            contract.get_queued_transfer(proof_key.clone()),
+            Some(QueuedTransfer {
+                sender: sender_eth_address(),
+                recipient: bob().to_string(),
+                amount: U128(100),
+                fee: U128(0),
+                volume_recorded: true,
+            })
+        );
+        assert_eq!(
+            near_sdk::test_utils::get_logs(),
+            vec![format!(
+                "EVENT_JSON:{}",
+                Event::DelayedTransferCancelled {
+                    proof_key: proof_key.clone(),
+                    cancelled_by: bob(),
+                }
+                .to_json_string()
+            )]
+        );
+
+        // The amount was added to the daily volume when the transfer was delayed.
+        assert_eq!(contract.get_daily_volume(Direction::ToNear), U128(100));
+        set_env!(
+            predecessor_account_id: alice_near_account(),
+            current_account_id: bridge_near_account(),
+        );
+        contract.approve_queued_transfer(proof_key);
+        assert_eq!(contract.get_daily_volume(Direction::ToNear), U128(100));
+    }
+
+    #[test]
+    #[should_panic(expected = "Delayed transfer can't be cancelled after its release")]
+    fn cancel_delayed_transfer_panics_after_release_timestamp() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+        let proof_key = finish_delayed_transfer(&mut contract, 100);
+
+        set_env!(
+            predecessor_account_id: alice_near_account(),
+            current_account_id: bridge_near_account(),
+            block_timestamp: 61 * NANOSECONDS_IN_SECOND,
+        );
+        contract.cancel_delayed_transfer(proof_key);
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient permissions for method")]
+    fn cancel_delayed_transfer_requires_dao_or_pause_manager() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+        let proof_key = finish_delayed_transfer(&mut contract, 100);
+
+        set_env!(
+            predecessor_account_id: bob(),
+            current_account_id: bridge_near_account(),
+        );
+        contract.cancel_delayed_transfer(proof_key);
+    }
+
+    #[test]
+    #[should_panic(expected = "Pausable: Method is paused")]
+    fn ft_on_transfer_panics_when_migration_is_paused() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+        contract.pa_pause_feature("migrate_to_ethereum".to_owned());
+
+        set_env!(
+            predecessor_account_id: wnear_near_account(),
+            current_account_id: bridge_near_account(),
+        );
+        contract.ft_on_transfer(bob(), U128(1000), alice_eth_address());
+    }
+
+    #[test]
+    fn ft_on_transfer_accepts_unrestricted_sender_when_migration_is_paused() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+        contract.pa_pause_feature("migrate_to_ethereum".to_owned());
+        contract.acl_grant_role(Role::UnrestrictedMigrateToEthereum.into(), bob());
+
+        set_env!(
+            predecessor_account_id: wnear_near_account(),
+            current_account_id: bridge_near_account(),
+        );
+        let unused_amount = contract.ft_on_transfer(bob(), U128(1000), alice_eth_address());
+        assert!(matches!(unused_amount, PromiseOrValue::Value(U128(0))));
+    }
+
+    #[test]
+    fn ft_on_transfer_uses_all_wnear() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        set_env!(
+            predecessor_account_id: wnear_near_account(),
+            current_account_id: bridge_near_account(),
+        );
+        let unused_amount =
+            contract.ft_on_transfer(alice_near_account(), U128(1000), alice_eth_address());
+        assert!(matches!(unused_amount, PromiseOrValue::Value(U128(0))));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only wNEAR can be migrated to Ethereum")]
+    fn ft_on_transfer_panics_when_token_is_not_wnear() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        set_env!(
+            predecessor_account_id: bob(),
+            current_account_id: bridge_near_account(),
+        );
+        contract.ft_on_transfer(alice_near_account(), U128(1000), alice_eth_address());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_ETH_ADDRESS")]
+    fn ft_on_transfer_panics_when_eth_address_is_invalid() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        set_env!(
+            predecessor_account_id: wnear_near_account(),
+            current_account_id: bridge_near_account(),
+        );
+        contract.ft_on_transfer(alice_near_account(), U128(1000), invalid_eth_address());
+    }
+
+    #[test]
+    fn finish_wnear_migration_records_migration() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        set_env!(
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
+        );
+        let amount = 1_000_000_000_000_000_000_000_000u128;
+        let recipient = get_eth_address(alice_eth_address());
+        let result =
+            contract.finish_wnear_migration(Ok(()), alice_near_account(), U128(amount), recipient);
+
+        let transfer = contract.get_outgoing_transfer(1).unwrap();
+        assert_eq!(transfer.sender, alice_near_account());
+        let storage_cost = contract.get_migration_deposit(alice_near_account()).0;
+        assert_eq!(transfer.amount, U128(amount - storage_cost));
+        match result {
+            PromiseOrValue::Value(result) => assert_eq!(
+                result,
+                ResultType::MigrateNearToEthereum {
+                    amount: transfer.amount.0,
+                    recipient,
+                }
+            ),
+            PromiseOrValue::Promise(_) => panic!("Migration wasn't recorded"),
+        }
+    }
+
+    #[test]
+    fn finish_wnear_migration_returns_near_when_rate_limit_is_exceeded() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+        contract.set_rate_limits(sample_rate_limits(false));
+
+        set_env!(
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
+        );
+        let amount = U128(1_000_000_000_000_000_000_000_000);
+        let result = contract.finish_wnear_migration(
+            Ok(()),
+            alice_near_account(),
+            amount,
+            get_eth_address(alice_eth_address()),
+        );
+
+        assert!(matches!(result, PromiseOrValue::Promise(_)));
+        assert_eq!(contract.get_outgoing_nonce(), 0);
+        assert_eq!(contract.get_outgoing_transfer(1), None);
+        assert_eq!(
+            near_sdk::test_utils::get_logs(),
+            vec![format!(
+                "EVENT_JSON:{}",
+                Event::MigrateToEthereumFailed {
+                    sender: alice_near_account(),
+                    amount,
+                    reason: "ERR_TRANSFER_LIMIT_EXCEEDED".to_owned(),
+                }
+                .to_json_string()
+            )]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Method is private")]
+    fn finish_wnear_migration_can_only_be_called_by_bridge() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        set_env!(
+            predecessor_account_id: alice_near_account(),
+            current_account_id: bridge_near_account(),
+        );
+        contract.finish_wnear_migration(
+            Ok(()),
+            alice_near_account(),
+            U128(1_000_000_000_000_000_000_000_000),
+            get_eth_address(alice_eth_address()),
+        );
+    }
+
+    #[test]
+    fn finish_eth_to_near_transfer_delivers_plain_wnear() {
+        set_env!(predecessor_account_id: alice_near_account());
 
-    use super::*;
-    use std::convert::TryInto;
-    use uint::rustc_hex::FromHex;
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
 
-    macro_rules! inner_set_env {
-        ($builder:ident) => {
-            $builder
-        };
+        set_env!(
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
+            attached_deposit: env::storage_byte_cost() * 1000,
+        );
+        let proof = create_proof(e_near_eth_address());
+        let recipient = format!("wnear@{}", bob());
+        contract.finish_eth_to_near_transfer(
+            Ok(true),
+            recipient.clone(),
+            10,
+            sender_eth_address(),
+            proof.get_key().unwrap(),
+            relayer_near_account(),
+        );
 
-        ($builder:ident, $key:ident:$value:expr $(,$key_tail:ident:$value_tail:expr)*) => {
-            {
-               $builder.$key($value.try_into().unwrap());
-               inner_set_env!($builder $(,$key_tail:$value_tail)*)
-            }
+        let transfer = TransferToNear {
+            proof_key: hex::encode(proof.get_key().unwrap()),
+            sender: sender_eth_address(),
+            amount: U128(10),
+            fee: U128(0),
+            recipient,
         };
+        assert_eq!(
+            near_sdk::test_utils::get_logs(),
+            vec![format!(
+                "EVENT_JSON:{}",
+                Event::FinalisedViaWnear(transfer).to_json_string()
+            )]
+        );
     }
 
-    macro_rules! set_env {
-        ($($key:ident:$value:expr),* $(,)?) => {
-            let mut builder = VMContextBuilder::new();
-            let mut builder = &mut builder;
-            builder = inner_set_env!(builder, $($key: $value),*);
-            testing_env!(builder.build());
-        };
-    }
+    #[test]
+    fn finish_eth_to_near_transfer_deposits_to_aurora() {
+        set_env!(predecessor_account_id: alice_near_account());
 
-    fn alice_near_account() -> AccountId {
-        "alice.near".parse().unwrap()
-    }
-    fn prover_near_account() -> AccountId {
-        "prover".parse().unwrap()
-    }
-    fn wnear_near_account() -> AccountId {
-        "wrap.near".parse().unwrap()
-    }
-    fn e_near_eth_address() -> String {
-        "68a3637ba6e75c0f66b61a42639c4e9fcd3d4824".to_string()
-    }
-    fn alice_eth_address() -> String {
-        "25ac31a08eba29067ba4637788d1dbfb893cebf1".to_string()
-    }
-    fn invalid_eth_address() -> String {
-        "25Ac31A08EBA29067Ba4637788d1DbFB893cEBf".to_string()
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        set_env!(
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
+            attached_deposit: env::storage_byte_cost() * 1000,
+        );
+        let proof = create_proof(e_near_eth_address());
+        contract.finish_eth_to_near_transfer(
+            Ok(true),
+            "aurora:0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_owned(),
+            10,
+            sender_eth_address(),
+            proof.get_key().unwrap(),
+            relayer_near_account(),
+        );
+
+        let receipts = near_sdk::test_utils::get_created_receipts();
+        let calls: Vec<_> = receipts
+            .iter()
+            .flat_map(|receipt| &receipt.actions)
+            .filter_map(|action| match action {
+                near_sdk::mock::VmAction::FunctionCall {
+                    function_name,
+                    args,
+                    ..
+                } => Some((function_name.as_str(), args.clone())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            calls.iter().map(|(name, _)| *name).collect::<Vec<_>>(),
+            vec!["near_deposit", "ft_transfer_call", "resolve_aurora_deposit"]
+        );
+        assert_eq!(
+            near_sdk::serde_json::from_slice::<near_sdk::serde_json::Value>(&calls[1].1).unwrap(),
+            near_sdk::serde_json::json!({
+                "receiver_id": "aurora",
+                "amount": "10",
+                "memo": null,
+                "msg": "5aaeb6053f3e94c9b9a09f33669435e7ef1beaed",
+            })
+        );
     }
 
-    fn sample_proof() -> Proof {
-        Proof {
-            log_index: 0,
-            log_entry_data: vec![],
-            receipt_index: 0,
-            receipt_data: vec![],
-            header_data: vec![],
-            proof: vec![],
-        }
+    fn omni_bridge_recipient() -> String {
+        r#"{"version":1,"receiver":"omni.bridge.near","delivery":"omni_bridge","msg":"sol:abc","refund_to":"alice.near"}"#
+            .to_owned()
     }
 
-    fn create_proof(e_near: String) -> Proof {
-        let event_data = TransferToNearInitiatedEvent {
-            e_near_address: e_near
-                .from_hex::<Vec<_>>()
-                .unwrap()
-                .as_slice()
-                .try_into()
-                .unwrap(),
-            sender: "00005474e89094c44da98b954eedeac495271d0f".to_string(),
-            amount: 1000,
-            recipient: "123".to_string(),
-        };
+    #[test]
+    fn finish_eth_to_near_transfer_rejects_not_whitelisted_omni_bridge() {
+        set_env!(predecessor_account_id: alice_near_account());
 
-        Proof {
-            log_index: 0,
-            log_entry_data: event_data.to_log_entry_data(),
-            receipt_index: 0,
-            receipt_data: vec![],
-            header_data: vec![],
-            proof: vec![],
-        }
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+        contract.add_omni_bridge("other.bridge.near".parse().unwrap());
+
+        set_env!(
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
+            attached_deposit: env::storage_byte_cost() * 1000,
+        );
+        let proof_key = create_proof(e_near_eth_address()).get_key().unwrap();
+        contract.finish_eth_to_near_transfer(
+            Ok(true),
+            omni_bridge_recipient(),
+            10,
+            sender_eth_address(),
+            proof_key.clone(),
+            relayer_near_account(),
+        );
+        assert_finalisation_failed(&contract, &proof_key, "ERR_OMNI_BRIDGE_NOT_WHITELISTED");
     }
 
     #[test]
-    fn can_migrate_near_to_eth_with_valid_params() {
+    fn finish_eth_to_near_transfer_forwards_to_omni_bridge() {
         set_env!(predecessor_account_id: alice_near_account());
 
         let mut contract = NearBridge::new(
@@ -420,20 +4681,56 @@ mod tests {
             e_near_eth_address(),
             wnear_near_account(),
         );
+        contract.add_omni_bridge("omni.bridge.near".parse().unwrap());
 
-        // lets deposit 1 Near
-        let deposit_amount = 1_000_000_000_000_000_000_000_000u128;
         set_env!(
-            predecessor_account_id: alice_near_account(),
-            attached_deposit: deposit_amount,
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
+            attached_deposit: env::storage_byte_cost() * 1000,
+        );
+        contract.finish_eth_to_near_transfer(
+            Ok(true),
+            omni_bridge_recipient(),
+            10,
+            sender_eth_address(),
+            create_proof(e_near_eth_address()).get_key().unwrap(),
+            relayer_near_account(),
         );
 
-        contract.migrate_to_ethereum(alice_eth_address());
+        let receipts = near_sdk::test_utils::get_created_receipts();
+        let calls: Vec<_> = receipts
+            .iter()
+            .flat_map(|receipt| &receipt.actions)
+            .filter_map(|action| match action {
+                near_sdk::mock::VmAction::FunctionCall {
+                    function_name,
+                    args,
+                    ..
+                } => Some((function_name.as_str(), args.clone())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            calls.iter().map(|(name, _)| *name).collect::<Vec<_>>(),
+            vec![
+                "near_deposit",
+                "ft_transfer_call",
+                "resolve_wnear_transfer_call"
+            ]
+        );
+        assert_eq!(
+            near_sdk::serde_json::from_slice::<near_sdk::serde_json::Value>(&calls[1].1).unwrap(),
+            near_sdk::serde_json::json!({
+                "receiver_id": "omni.bridge.near",
+                "amount": "10",
+                "memo": null,
+                "msg": "sol:abc",
+            })
+        );
     }
 
     #[test]
-    #[should_panic]
-    fn migrate_near_to_eth_panics_when_attached_deposit_is_zero() {
+    fn test_omni_bridges_whitelist() {
         set_env!(predecessor_account_id: alice_near_account());
 
         let mut contract = NearBridge::new(
@@ -441,13 +4738,20 @@ mod tests {
             e_near_eth_address(),
             wnear_near_account(),
         );
+        assert!(contract.get_omni_bridges().is_empty());
 
-        contract.migrate_to_ethereum(alice_eth_address());
+        let omni_bridge: AccountId = "omni.bridge.near".parse().unwrap();
+        contract.add_omni_bridge(omni_bridge.clone());
+        contract.add_omni_bridge(omni_bridge.clone());
+        assert_eq!(contract.get_omni_bridges(), vec![omni_bridge.clone()]);
+
+        contract.remove_omni_bridge(omni_bridge);
+        assert!(contract.get_omni_bridges().is_empty());
     }
 
     #[test]
-    #[should_panic]
-    fn migrate_near_to_eth_panics_when_contract_is_paused() {
+    #[should_panic(expected = "Insufficient permissions for method")]
+    fn add_omni_bridge_can_only_be_called_by_dao() {
         set_env!(predecessor_account_id: alice_near_account());
 
         let mut contract = NearBridge::new(
@@ -456,21 +4760,61 @@ mod tests {
             wnear_near_account(),
         );
 
-        contract.pa_pause_feature("migrate_to_ethereum".to_owned());
+        set_env!(predecessor_account_id: bob());
+        contract.add_omni_bridge(bob());
+    }
+
+    #[test]
+    fn send_to_omni_bridge_keeps_reserve() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+        contract.set_omni_bridge_account_id("omni.bridge.near".parse().unwrap());
+        contract.acl_grant_role(Role::LiquiditySweeper.into(), bob());
 
-        // lets deposit 1 Near
-        let deposit_amount = 1_000_000_000_000_000_000_000_000u128;
         set_env!(
             predecessor_account_id: bob(),
-            attached_deposit: deposit_amount,
+            current_account_id: bridge_near_account(),
         );
+        let available_balance = contract.get_avialable_balance().0;
+        let reserve = available_balance / 4;
+        contract.send_to_omni_bridge(None, Some(U128(reserve)));
 
-        contract.migrate_to_ethereum(alice_eth_address());
+        let receipts = near_sdk::test_utils::get_created_receipts();
+        let calls: Vec<_> = receipts
+            .iter()
+            .flat_map(|receipt| &receipt.actions)
+            .filter_map(|action| match action {
+                near_sdk::mock::VmAction::FunctionCall {
+                    function_name,
+                    args,
+                    deposit,
+                    ..
+                } => Some((function_name.as_str(), args.clone(), *deposit)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            calls.iter().map(|(name, _, _)| *name).collect::<Vec<_>>(),
+            vec!["near_deposit", "ft_transfer", "resolve_omni_bridge_sweep"]
+        );
+        assert_eq!(calls[0].2, available_balance - reserve);
+        assert_eq!(
+            near_sdk::serde_json::from_slice::<near_sdk::serde_json::Value>(&calls[1].1).unwrap(),
+            near_sdk::serde_json::json!({
+                "receiver_id": "omni.bridge.near",
+                "amount": (available_balance - reserve).to_string(),
+                "memo": null,
+            })
+        );
     }
 
     #[test]
-    #[should_panic]
-    fn migrate_near_to_eth_panics_when_eth_address_is_invalid() {
+    fn outstanding_payouts_are_not_available() {
         set_env!(predecessor_account_id: alice_near_account());
 
         let mut contract = NearBridge::new(
@@ -478,13 +4822,52 @@ mod tests {
             e_near_eth_address(),
             wnear_near_account(),
         );
+        let proof_key = finish_delayed_transfer(&mut contract, 100);
+        assert_eq!(contract.get_outstanding_payouts(), U128(100));
 
-        contract.migrate_to_ethereum(invalid_eth_address());
+        set_env!(
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
+        );
+        contract.resolve_native_transfer(
+            Err(PromiseError::Failed),
+            bob(),
+            U128(100),
+            "ab".to_owned(),
+            None,
+        );
+        contract.resolve_wnear_transfer_call(
+            Ok(U128(70)),
+            bob(),
+            U128(100),
+            "cd".to_owned(),
+            Some(alice_near_account()),
+        );
+        assert_eq!(contract.get_outstanding_payouts(), U128(230));
+        assert_eq!(
+            contract.get_avialable_balance().0,
+            env::account_balance()
+                - env::storage_byte_cost() * Balance::from(env::storage_usage())
+                - 230
+        );
+
+        set_env!(
+            predecessor_account_id: alice_near_account(),
+            current_account_id: bridge_near_account(),
+        );
+        contract.redirect_transfer("ab".to_owned(), alice_near_account());
+        contract.claim_refund();
+        assert_eq!(contract.get_outstanding_payouts(), U128(100));
+
+        contract.cancel_delayed_transfer(proof_key.clone());
+        assert_eq!(contract.get_outstanding_payouts(), U128(100));
+        contract.approve_queued_transfer(proof_key);
+        assert_eq!(contract.get_outstanding_payouts(), U128(0));
     }
 
     #[test]
-    #[should_panic]
-    fn finalise_eth_to_near_transfer_panics_when_contract_is_paused() {
+    #[should_panic(expected = "Invalid amount to send to the omni bridge")]
+    fn send_to_omni_bridge_rejects_amount_above_reserve() {
         set_env!(predecessor_account_id: alice_near_account());
 
         let mut contract = NearBridge::new(
@@ -492,15 +4875,30 @@ mod tests {
             e_near_eth_address(),
             wnear_near_account(),
         );
+        contract.set_omni_bridge_account_id("omni.bridge.near".parse().unwrap());
 
-        contract.pa_pause_feature("finalise_eth_to_near_transfer".to_owned());
+        let available_balance = contract.get_avialable_balance().0;
+        contract.send_to_omni_bridge(
+            Some(U128(available_balance / 2 + 1)),
+            Some(U128(available_balance / 2)),
+        );
+    }
 
-        contract.finalise_eth_to_near_transfer(sample_proof());
+    #[test]
+    #[should_panic(expected = "Omni bridge address hasn't been set")]
+    fn send_to_omni_bridge_requires_omni_bridge_account() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+        contract.send_to_omni_bridge(Some(U128(1)), None);
     }
 
     #[test]
-    #[should_panic]
-    fn finalise_eth_to_near_transfer_panics_when_event_originates_from_wrong_contract() {
+    fn resolve_omni_bridge_sweep_records_swept_liquidity() {
         set_env!(predecessor_account_id: alice_near_account());
 
         let mut contract = NearBridge::new(
@@ -509,12 +4907,26 @@ mod tests {
             wnear_near_account(),
         );
 
-        contract.finalise_eth_to_near_transfer(create_proof(alice_eth_address()));
+        set_env!(
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
+        );
+        let omni_bridge: AccountId = "omni.bridge.near".parse().unwrap();
+        assert!(contract.resolve_omni_bridge_sweep(Ok(()), omni_bridge.clone(), U128(10)));
+        assert!(contract.resolve_omni_bridge_sweep(Ok(()), omni_bridge.clone(), U128(5)));
+        assert!(!contract.resolve_omni_bridge_sweep(
+            Err(PromiseError::Failed),
+            omni_bridge,
+            U128(20)
+        ));
+
+        let liquidity = contract.get_omni_bridge_liquidity();
+        assert_eq!(liquidity.swept, U128(15));
+        assert_eq!(liquidity.locked, U128(20));
     }
 
     #[test]
-    #[should_panic(expected = "Attached deposit is not sufficient to record proof")]
-    fn finish_eth_to_near_transfer_panics_if_attached_deposit_is_not_sufficient_to_record_proof() {
+    fn resolve_aurora_deposit_adds_pending_transfer_for_unused_amount() {
         set_env!(predecessor_account_id: alice_near_account());
 
         let mut contract = NearBridge::new(
@@ -523,16 +4935,42 @@ mod tests {
             wnear_near_account(),
         );
 
-        contract.finish_eth_to_near_transfer(
-            true,
-            bob().to_string(),
-            10,
-            create_proof(e_near_eth_address()),
+        set_env!(
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
+        );
+        let engine_id: AccountId = "aurora".parse().unwrap();
+        assert_eq!(
+            contract.resolve_aurora_deposit(
+                Ok(U128(10)),
+                engine_id.clone(),
+                U128(10),
+                "cd".to_owned()
+            ),
+            U128(10)
+        );
+        assert_eq!(contract.get_pending_transfer("cd".to_owned()), None);
+
+        assert_eq!(
+            contract.resolve_aurora_deposit(
+                Err(PromiseError::Failed),
+                engine_id.clone(),
+                U128(10),
+                "cd".to_owned()
+            ),
+            U128(0)
+        );
+        assert_eq!(
+            contract.get_pending_transfer("cd".to_owned()),
+            Some(PendingTransfer {
+                recipient: engine_id,
+                amount: U128(10),
+            })
         );
     }
 
     #[test]
-    fn finalise_eth_to_near_transfer_works_with_valid_params() {
+    fn send_wnear_transfer_registers_recipient() {
         set_env!(predecessor_account_id: alice_near_account());
 
         let mut contract = NearBridge::new(
@@ -541,41 +4979,168 @@ mod tests {
             wnear_near_account(),
         );
 
-        // Alice deposit 1 Near to migrate to eth
-        let deposit_amount = 1_000_000_000_000_000_000_000_000u128;
         set_env!(
-            predecessor_account_id: alice_near_account(),
-            attached_deposit: deposit_amount,
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
         );
+        let amount = WNEAR_STORAGE_DEPOSIT + 10;
+        contract.send_wnear_transfer(Ok(None), bob(), U128(amount), "cd".to_owned(), None, None);
 
-        contract.migrate_to_ethereum(alice_eth_address());
+        let receipts = near_sdk::test_utils::get_created_receipts();
+        let method_names: Vec<_> = receipts
+            .iter()
+            .flat_map(|receipt| &receipt.actions)
+            .filter_map(|action| match action {
+                near_sdk::mock::VmAction::FunctionCall { function_name, .. } => {
+                    Some(function_name.as_str())
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            method_names,
+            vec![
+                "storage_deposit",
+                "near_deposit",
+                "ft_transfer",
+                "resolve_wnear_transfer"
+            ]
+        );
+    }
 
-        // todo adjust attached deposit down
+    #[test]
+    fn resolve_wnear_transfer_adds_pending_transfer_when_transfer_failed() {
+        set_env!(predecessor_account_id: alice_near_account());
 
-        // Lets suppose Alice migrates back
-        contract.finalise_eth_to_near_transfer(create_proof(e_near_eth_address()));
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
 
-        // todo asserts i.e. that alice has received the 1 near back etc.
+        set_env!(
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
+        );
+        assert!(contract.resolve_wnear_transfer(Ok(()), bob(), U128(10), "cd".to_owned(), None));
+        assert_eq!(contract.get_pending_transfer("cd".to_owned()), None);
+
+        assert!(!contract.resolve_wnear_transfer(
+            Err(PromiseError::Failed),
+            bob(),
+            U128(10),
+            "cd".to_owned(),
+            None
+        ));
+        assert_eq!(
+            contract.get_pending_transfer("cd".to_owned()),
+            Some(PendingTransfer {
+                recipient: bob(),
+                amount: U128(10),
+            })
+        );
     }
 
     #[test]
-    fn test_set_wnear_account_id() {
+    #[should_panic(expected = "Method is private")]
+    fn send_wnear_transfer_can_only_be_called_by_bridge() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
         set_env!(
             predecessor_account_id: alice_near_account(),
-            signer_account_id: alice_near_account()
+            current_account_id: bridge_near_account(),
+        );
+        contract.send_wnear_transfer(Ok(None), bob(), U128(10), "cd".to_owned(), None, None);
+    }
+
+    #[test]
+    fn failed_payouts_go_to_refund_to_account() {
+        set_env!(predecessor_account_id: alice_near_account());
+
+        let mut contract = NearBridge::new(
+            prover_near_account(),
+            e_near_eth_address(),
+            wnear_near_account(),
+        );
+
+        set_env!(
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
+        );
+        contract.resolve_wnear_transfer_call(
+            Ok(U128(70)),
+            bob(),
+            U128(100),
+            "ab".to_owned(),
+            Some(alice_near_account()),
+        );
+        assert_eq!(contract.get_refund(bob()), U128(0));
+        assert_eq!(contract.get_refund(alice_near_account()), U128(30));
+
+        contract.resolve_native_transfer(
+            Err(PromiseError::Failed),
+            bob(),
+            U128(100),
+            "cd".to_owned(),
+            Some(alice_near_account()),
+        );
+        assert_eq!(
+            contract.get_pending_transfer("cd".to_owned()),
+            Some(PendingTransfer {
+                recipient: alice_near_account(),
+                amount: U128(100),
+            })
         );
+    }
+
+    #[test]
+    fn finish_eth_to_near_transfer_accepts_json_recipient() {
+        set_env!(predecessor_account_id: alice_near_account());
 
         let mut contract = NearBridge::new(
             prover_near_account(),
             e_near_eth_address(),
-            "old_wnear_near.near".parse().unwrap(),
+            wnear_near_account(),
         );
 
-        contract.set_wnear_account_id(wnear_near_account());
+        set_env!(
+            predecessor_account_id: bridge_near_account(),
+            current_account_id: bridge_near_account(),
+            attached_deposit: env::storage_byte_cost() * 1000,
+        );
+        let proof = create_proof(e_near_eth_address());
+        let recipient = format!(
+            r#"{{"version":1,"receiver":"{}","msg":"msg","memo":"memo"}}"#,
+            bob()
+        );
+        contract.finish_eth_to_near_transfer(
+            Ok(true),
+            recipient.clone(),
+            10,
+            sender_eth_address(),
+            proof.get_key().unwrap(),
+            relayer_near_account(),
+        );
 
+        let transfer = TransferToNear {
+            proof_key: hex::encode(proof.get_key().unwrap()),
+            sender: sender_eth_address(),
+            amount: U128(10),
+            fee: U128(0),
+            recipient,
+        };
         assert_eq!(
-            contract.get_wnear_account_id().unwrap(),
-            wnear_near_account()
+            near_sdk::test_utils::get_logs(),
+            vec![format!(
+                "EVENT_JSON:{}",
+                Event::FinalisedViaWnear(transfer).to_json_string()
+            )]
         );
     }
 }
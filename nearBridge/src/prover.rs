@@ -4,42 +4,162 @@ use eth_types::*;
 use ethabi::param_type::Writer;
 use ethabi::{Event, EventParam, Hash, Log, ParamType, RawLog, Token};
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::{env, ext_contract, AccountId};
+use near_sdk::serde::Deserialize;
+use near_sdk::{env, ext_contract, serde_json, AccountId};
 use tiny_keccak::Keccak;
 
-pub type EthAddress = [u8; 20];
+pub use crate::eth_address::EthAddress;
+use crate::BridgeError;
+
+/// Prefix of the recipients which receive the transfer as wNEAR without a message.
+pub const WNEAR_RECIPIENT_PREFIX: &str = "wnear@";
+/// Aurora engine, which mints the wNEAR deposited with `ft_transfer_call` to the EVM address
+/// from the message.
+pub const AURORA_ENGINE_ACCOUNT_ID: &str = "aurora";
+/// Version of the JSON recipient format supported by the bridge.
+pub const JSON_RECIPIENT_VERSION: u8 = 1;
 
 #[derive(Debug, PartialEq)]
 pub struct Recipient {
     pub target: AccountId,
-    pub message: Option<String>,
+    pub delivery: Delivery,
+    /// Account which gets the unused amount and the failed payouts instead of the target.
+    pub refund_to: Option<AccountId>,
 }
 
-pub fn is_valid_eth_address(address: String) -> bool {
-    if hex::decode(address.clone()).is_err() {
-        return false;
-    }
+/// How the transferred amount is paid out to the recipient.
+#[derive(Debug, PartialEq)]
+pub enum Delivery {
+    /// Native NEAR transfer.
+    Native,
+    /// wNEAR `ft_transfer`, registering the recipient on the wNEAR contract if needed.
+    Wnear { memo: Option<String> },
+    /// wNEAR `ft_transfer_call` with the message.
+    WnearTransferCall { msg: String, memo: Option<String> },
+    /// wNEAR deposit to the address in Aurora with `ft_transfer_call` to the Aurora engine.
+    Aurora { address: EthAddress },
+    /// wNEAR `ft_transfer_call` to an omni bridge with the message describing the transfer to
+    /// the destination chain. The omni bridge has to be whitelisted by the DAO.
+    OmniBridge { msg: String, memo: Option<String> },
+}
+
+/// Recipient in the JSON format, e.g.
+/// `{"version":1,"receiver":"alice.near","delivery":"wnear","memo":"invoice 42"}`.
+/// The delivery defaults to `wnear` if there is a message and to `native` otherwise.
+/// With the `omni_bridge` delivery the receiver is the omni bridge and the message and
+/// `refund_to` are required, since the unused amount can't be returned to the omni bridge.
+#[derive(Deserialize)]
+#[serde(crate = "near_sdk::serde", deny_unknown_fields)]
+struct JsonRecipient {
+    version: u8,
+    receiver: AccountId,
+    delivery: Option<JsonDelivery>,
+    msg: Option<String>,
+    memo: Option<String>,
+    refund_to: Option<AccountId>,
+}
 
-    hex::decode(address).unwrap().len() == 20
+#[derive(Deserialize)]
+#[serde(crate = "near_sdk::serde", rename_all = "snake_case")]
+enum JsonDelivery {
+    Native,
+    Wnear,
+    OmniBridge,
+}
+
+pub fn is_valid_eth_address(address: String) -> bool {
+    address.parse::<EthAddress>().is_ok()
 }
 
 pub fn get_eth_address(address: String) -> EthAddress {
-    let data = hex::decode(address).expect("address should be a valid hex string.");
-    assert_eq!(data.len(), 20, "address should be 20 bytes long");
-    let mut result = [0u8; 20];
-    result.copy_from_slice(&data);
-    result
+    address
+        .parse()
+        .unwrap_or_else(|error: BridgeError| env::panic_str(error.as_str()))
 }
 
+/// Parses the recipient of an Eth→NEAR transfer: `<account>` for native NEAR,
+/// `<account>:<msg>` for wNEAR sent with `ft_transfer_call`, `wnear@<account>` for wNEAR
+/// sent with `ft_transfer`, `aurora:0x<address>` for wNEAR deposited to the address in Aurora
+/// and a JSON object starting with `{` for the other options.
 pub fn parse_recipient(recipient: &str) -> Option<Recipient> {
-    let (target, message) = recipient.split_once(':').map_or_else(
-        || (recipient, None),
-        |(recipient, msg)| (recipient, Some(msg.to_owned())),
+    if recipient.starts_with('{') {
+        return parse_json_recipient(recipient);
+    }
+
+    // Only `aurora:0x<address>` is a deposit to Aurora, other messages to the engine are passed
+    // with `ft_transfer_call` as is.
+    if let Some(address) = recipient
+        .strip_prefix(AURORA_ENGINE_ACCOUNT_ID)
+        .and_then(|address| address.strip_prefix(':'))
+        .filter(|address| address.starts_with("0x"))
+    {
+        return Some(Recipient {
+            target: AURORA_ENGINE_ACCOUNT_ID.parse().ok()?,
+            delivery: Delivery::Aurora {
+                address: address.parse().ok()?,
+            },
+            refund_to: None,
+        });
+    }
+
+    if let Some(target) = recipient.strip_prefix(WNEAR_RECIPIENT_PREFIX) {
+        return Some(Recipient {
+            target: target.parse().ok()?,
+            delivery: Delivery::Wnear { memo: None },
+            refund_to: None,
+        });
+    }
+
+    let (target, delivery) = recipient.split_once(':').map_or_else(
+        || (recipient, Delivery::Native),
+        |(recipient, msg)| {
+            (
+                recipient,
+                Delivery::WnearTransferCall {
+                    msg: msg.to_owned(),
+                    memo: None,
+                },
+            )
+        },
     );
 
     Some(Recipient {
         target: target.parse().ok()?,
-        message,
+        delivery,
+        refund_to: None,
+    })
+}
+
+/// Parses the JSON recipient. Unknown versions and fields, a message or a memo with the native
+/// delivery and the omni bridge delivery without `refund_to` are rejected.
+fn parse_json_recipient(recipient: &str) -> Option<Recipient> {
+    let recipient: JsonRecipient = serde_json::from_str(recipient).ok()?;
+    if recipient.version != JSON_RECIPIENT_VERSION {
+        return None;
+    }
+
+    let delivery = match (recipient.delivery, recipient.msg) {
+        (None | Some(JsonDelivery::Native), None) if recipient.memo.is_none() => Delivery::Native,
+        (None | Some(JsonDelivery::Wnear), Some(msg)) => Delivery::WnearTransferCall {
+            msg,
+            memo: recipient.memo,
+        },
+        (Some(JsonDelivery::Wnear), None) => Delivery::Wnear {
+            memo: recipient.memo,
+        },
+        (Some(JsonDelivery::OmniBridge), Some(msg)) if recipient.refund_to.is_some() => {
+            Delivery::OmniBridge {
+                msg,
+                memo: recipient.memo,
+            }
+        }
+        _ => return None,
+    };
+
+    Some(Recipient {
+        target: recipient.receiver,
+        delivery,
+        refund_to: recipient.refund_to,
     })
 }
 
@@ -69,7 +189,31 @@ pub struct Proof {
 }
 
 impl Proof {
-    pub fn get_key(&self) -> Vec<u8> {
+    /// Identity of the proven event: the hash of the block, the receipt index and the log index.
+    /// The block hash is computed from the decoded RLP header only, so the key doesn't depend on
+    /// how `header_data` is passed, e.g. with trailing bytes.
+    pub fn get_key(&self) -> Result<Vec<u8>, BridgeError> {
+        let header_len = rlp::Rlp::new(&self.header_data)
+            .payload_info()
+            .map_err(|_| BridgeError::InvalidBlockHeader)?
+            .total();
+        let header_data = self
+            .header_data
+            .get(..header_len)
+            .ok_or(BridgeError::InvalidBlockHeader)?;
+        let header: BlockHeader =
+            rlp::decode(header_data).map_err(|_| BridgeError::InvalidBlockHeader)?;
+        let block_hash = header.hash.ok_or(BridgeError::InvalidBlockHeader)?;
+
+        let mut data = block_hash.0.as_bytes().to_vec();
+        data.extend(self.receipt_index.try_to_vec().unwrap());
+        data.extend(self.log_index.try_to_vec().unwrap());
+        Ok(env::sha256(&data))
+    }
+
+    /// Key of the event which was used before `get_key`. The events finalised earlier are
+    /// recorded under it.
+    pub fn get_legacy_key(&self) -> Vec<u8> {
         let mut data = self.log_index.try_to_vec().unwrap();
         data.extend(self.receipt_index.try_to_vec().unwrap());
         data.extend(self.header_data.clone());
@@ -85,7 +229,11 @@ pub struct EthEvent {
 }
 
 impl EthEvent {
-    pub fn from_log_entry_data(name: &str, params: EthEventParams, data: &[u8]) -> Self {
+    pub fn from_log_entry_data(
+        name: &str,
+        params: EthEventParams,
+        data: &[u8],
+    ) -> Result<Self, BridgeError> {
         let event = Event {
             name: name.to_string(),
             inputs: params
@@ -98,24 +246,30 @@ impl EthEvent {
                 .collect(),
             anonymous: false,
         };
-        let log_entry: LogEntry = rlp::decode(data).expect("Invalid RLP");
-        let locker_address = (log_entry.address.clone().0).0;
-        let topics = log_entry
+        let log_entry: LogEntry = rlp::decode(data).map_err(|_| BridgeError::InvalidRlp)?;
+        let locker_address = EthAddress((log_entry.address.0).0);
+        let topics: Vec<Hash> = log_entry
             .topics
             .iter()
             .map(|h| Hash::from(&((h.0).0)))
             .collect();
 
+        if topics.first() != Some(&event.signature()) {
+            return Err(BridgeError::WrongTopic);
+        }
+
         let raw_log = RawLog {
             topics,
-            data: log_entry.data.clone(),
+            data: log_entry.data,
         };
 
-        let log = event.parse_log(raw_log).expect("Failed to parse event log");
-        Self {
+        let log = event
+            .parse_log(raw_log)
+            .map_err(|_| BridgeError::InvalidEventData)?;
+        Ok(Self {
             locker_address,
             log,
-        }
+        })
     }
 
     pub fn to_log_entry_data(
@@ -140,7 +294,7 @@ impl EthEvent {
         let params: Vec<ParamType> = event.inputs.iter().map(|p| p.kind.clone()).collect();
         let topics = indexes.into_iter().map(|value| H256::from(value)).collect();
         let log_entry = LogEntry {
-            address: locker_address.into(),
+            address: locker_address.0.into(),
             topics: vec![vec![long_signature(&event.name, &params).0.into()], topics].concat(),
             data: ethabi::encode(&values),
         };
@@ -171,13 +325,100 @@ fn fill_signature(name: &str, params: &[ParamType], result: &mut [u8]) {
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    fn proof_with_header(header_data: Vec<u8>) -> Proof {
+        Proof {
+            log_index: 1,
+            receipt_index: 2,
+            header_data,
+            ..Default::default()
+        }
+    }
+
+    fn sample_header() -> BlockHeader {
+        BlockHeader {
+            number: 42,
+            timestamp: 1_700_000_000,
+            extra_data: vec![1, 2, 3],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_proof_key_is_stable_across_header_encodings() {
+        let header_data = rlp::encode(&sample_header()).to_vec();
+        let proof = proof_with_header(header_data.clone());
+
+        let mut padded_header_data = header_data.clone();
+        padded_header_data.extend([0u8; 4]);
+        let padded_proof = proof_with_header(padded_header_data.clone());
+        assert_eq!(proof.get_key(), padded_proof.get_key());
+        assert_ne!(proof.get_legacy_key(), padded_proof.get_legacy_key());
+
+        let decoded: BlockHeader = rlp::Rlp::new(&padded_header_data).as_val().unwrap();
+        let reencoded_proof = proof_with_header(rlp::encode(&decoded).to_vec());
+        assert_eq!(proof.get_key(), reencoded_proof.get_key());
+    }
+
+    #[test]
+    fn test_proof_key_uses_block_hash() {
+        let header_data = rlp::encode(&sample_header()).to_vec();
+        let proof = proof_with_header(header_data.clone());
+
+        let mut data = near_keccak256(&header_data).to_vec();
+        data.extend(2u64.to_le_bytes());
+        data.extend(1u64.to_le_bytes());
+        assert_eq!(proof.get_key(), Ok(env::sha256(&data)));
+        assert_ne!(proof.get_key(), Ok(proof.get_legacy_key()));
+    }
+
+    #[test]
+    fn test_proof_key_identifies_event() {
+        let header_data = rlp::encode(&sample_header()).to_vec();
+        let proof = proof_with_header(header_data.clone());
+
+        let other_log = Proof {
+            log_index: 2,
+            ..proof_with_header(header_data.clone())
+        };
+        let other_receipt = Proof {
+            receipt_index: 1,
+            ..proof_with_header(header_data)
+        };
+        let other_block = proof_with_header(
+            rlp::encode(&BlockHeader {
+                number: 43,
+                ..sample_header()
+            })
+            .to_vec(),
+        );
+
+        let key = proof.get_key().unwrap();
+        assert_ne!(other_log.get_key().unwrap(), key);
+        assert_ne!(other_receipt.get_key().unwrap(), key);
+        assert_ne!(other_block.get_key().unwrap(), key);
+        assert_ne!(proof.get_legacy_key(), key);
+    }
+
+    #[test]
+    fn test_proof_key_rejects_invalid_header() {
+        assert_eq!(
+            proof_with_header(vec![]).get_key(),
+            Err(BridgeError::InvalidBlockHeader)
+        );
+        assert_eq!(
+            proof_with_header(vec![0xc0]).get_key(),
+            Err(BridgeError::InvalidBlockHeader)
+        );
+    }
     #[test]
     fn test_parse_recipient() {
         assert_eq!(
             parse_recipient("test.near").unwrap(),
             Recipient {
                 target: "test.near".parse().unwrap(),
-                message: None,
+                delivery: Delivery::Native,
+                refund_to: None,
             }
         );
 
@@ -185,7 +426,11 @@ mod tests {
             parse_recipient("test.near:unwrap").unwrap(),
             Recipient {
                 target: "test.near".parse().unwrap(),
-                message: Some("unwrap".to_owned()),
+                delivery: Delivery::WnearTransferCall {
+                    msg: "unwrap".to_owned(),
+                    memo: None,
+                },
+                refund_to: None,
             }
         );
 
@@ -193,7 +438,11 @@ mod tests {
             parse_recipient("test.near:some_msg:with_extra_colon").unwrap(),
             Recipient {
                 target: "test.near".parse().unwrap(),
-                message: Some("some_msg:with_extra_colon".to_owned()),
+                delivery: Delivery::WnearTransferCall {
+                    msg: "some_msg:with_extra_colon".to_owned(),
+                    memo: None,
+                },
+                refund_to: None,
             }
         );
 
@@ -201,14 +450,168 @@ mod tests {
             parse_recipient("test.near:").unwrap(),
             Recipient {
                 target: "test.near".parse().unwrap(),
-                message: Some("".to_owned()),
+                delivery: Delivery::WnearTransferCall {
+                    msg: "".to_owned(),
+                    memo: None,
+                },
+                refund_to: None,
+            }
+        );
+
+        assert_eq!(
+            parse_recipient("wnear@test.near").unwrap(),
+            Recipient {
+                target: "test.near".parse().unwrap(),
+                delivery: Delivery::Wnear { memo: None },
+                refund_to: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_aurora_recipient() {
+        let address: EthAddress = "5aaeb6053f3e94c9b9a09f33669435e7ef1beaed".parse().unwrap();
+        for recipient in [
+            "aurora:0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed",
+            "aurora:0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+        ] {
+            assert_eq!(
+                parse_recipient(recipient).unwrap(),
+                Recipient {
+                    target: "aurora".parse().unwrap(),
+                    delivery: Delivery::Aurora { address },
+                    refund_to: None,
+                }
+            );
+        }
+
+        // Messages without the `0x` prefix are sent to the engine as is.
+        assert_eq!(
+            parse_recipient("aurora:5aaeb6053f3e94c9b9a09f33669435e7ef1beaed").unwrap(),
+            Recipient {
+                target: "aurora".parse().unwrap(),
+                delivery: Delivery::WnearTransferCall {
+                    msg: "5aaeb6053f3e94c9b9a09f33669435e7ef1beaed".to_owned(),
+                    memo: None,
+                },
+                refund_to: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_json_recipient() {
+        assert_eq!(
+            parse_recipient(r#"{"version":1,"receiver":"test.near"}"#).unwrap(),
+            Recipient {
+                target: "test.near".parse().unwrap(),
+                delivery: Delivery::Native,
+                refund_to: None,
+            }
+        );
+
+        assert_eq!(
+            parse_recipient(
+                r#"{"version":1,"receiver":"test.near","delivery":"native","refund_to":"alice.near"}"#
+            )
+            .unwrap(),
+            Recipient {
+                target: "test.near".parse().unwrap(),
+                delivery: Delivery::Native,
+                refund_to: Some("alice.near".parse().unwrap()),
+            }
+        );
+
+        assert_eq!(
+            parse_recipient(
+                r#"{"version":1,"receiver":"test.near","delivery":"wnear","memo":"memo"}"#
+            )
+            .unwrap(),
+            Recipient {
+                target: "test.near".parse().unwrap(),
+                delivery: Delivery::Wnear {
+                    memo: Some("memo".to_owned())
+                },
+                refund_to: None,
+            }
+        );
+
+        assert_eq!(
+            parse_recipient(r#"{"version":1,"receiver":"test.near","msg":"{\"a\":1}:b"}"#).unwrap(),
+            Recipient {
+                target: "test.near".parse().unwrap(),
+                delivery: Delivery::WnearTransferCall {
+                    msg: r#"{"a":1}:b"#.to_owned(),
+                    memo: None,
+                },
+                refund_to: None,
             }
         );
+
+        assert_eq!(
+            parse_recipient(
+                r#"{"version":1,"receiver":"test.near","delivery":"wnear","msg":"","memo":"memo","refund_to":"alice.near"}"#
+            )
+            .unwrap(),
+            Recipient {
+                target: "test.near".parse().unwrap(),
+                delivery: Delivery::WnearTransferCall {
+                    msg: "".to_owned(),
+                    memo: Some("memo".to_owned()),
+                },
+                refund_to: Some("alice.near".parse().unwrap()),
+            }
+        );
+
+        assert_eq!(
+            parse_recipient(
+                r#"{"version":1,"receiver":"omni.bridge.near","delivery":"omni_bridge","msg":"{\"recipient\":\"sol:abc\"}","refund_to":"alice.near"}"#
+            )
+            .unwrap(),
+            Recipient {
+                target: "omni.bridge.near".parse().unwrap(),
+                delivery: Delivery::OmniBridge {
+                    msg: r#"{"recipient":"sol:abc"}"#.to_owned(),
+                    memo: None,
+                },
+                refund_to: Some("alice.near".parse().unwrap()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_json_recipient() {
+        for recipient in [
+            "{",
+            "{}",
+            r#"{"receiver":"test.near"}"#,
+            r#"{"version":2,"receiver":"test.near"}"#,
+            r#"{"version":1}"#,
+            r#"{"version":1,"receiver":"test@.near"}"#,
+            r#"{"version":1,"receiver":"test.near","refund_to":"test@.near"}"#,
+            r#"{"version":1,"receiver":"test.near","delivery":"aurora"}"#,
+            r#"{"version":1,"receiver":"test.near","delivery":"native","msg":"msg"}"#,
+            r#"{"version":1,"receiver":"test.near","delivery":"native","memo":"memo"}"#,
+            r#"{"version":1,"receiver":"test.near","memo":"memo"}"#,
+            r#"{"version":1,"receiver":"test.near","delivery":"omni_bridge"}"#,
+            r#"{"version":1,"receiver":"test.near","delivery":"omni_bridge","msg":"msg"}"#,
+            r#"{"version":1,"receiver":"test.near","unknown":true}"#,
+            r#"{"version":1,"receiver":"test.near"}:msg"#,
+        ] {
+            assert!(parse_recipient(recipient).is_none(), "{}", recipient);
+        }
     }
 
     #[test]
     fn test_parse_invalid_recipient() {
         assert!(parse_recipient("test@.near").is_none());
         assert!(parse_recipient("test@.near:msg").is_none());
+        assert!(parse_recipient("wnear@test.near:msg").is_none());
+        assert!(parse_recipient("wnear@").is_none());
+        assert!(parse_recipient("aurora:0x").is_none());
+        assert!(parse_recipient("aurora:0x5aaeb6053f3e94c9b9a09f33669435e7ef1bea").is_none());
+        assert!(parse_recipient("aurora:0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD").is_none());
+        assert!(parse_recipient("aurora:0x0000000000000000000000000000000000000000").is_none());
+        assert!(parse_recipient("aurora:0x0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed").is_none());
     }
 }
@@ -0,0 +1,136 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::Balance;
+
+use crate::{BridgeError, Direction};
+
+/// Duration of a bucket of the volume window in nanoseconds.
+const BUCKET_DURATION: u64 = 60 * 60 * 1_000_000_000;
+/// Number of buckets covering the rolling 24 hours.
+const WINDOW_BUCKETS: u64 = 24;
+
+/// Limits of the transfers in one direction. Nothing is limited if not set.
+#[derive(
+    Debug, Default, Clone, Eq, PartialEq, BorshSerialize, BorshDeserialize, Serialize, Deserialize,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RateLimit {
+    /// Maximum amount of a single transfer.
+    pub max_transfer: Option<U128>,
+    /// Maximum amount transferred within the rolling 24 hours.
+    pub max_daily_volume: Option<U128>,
+}
+
+impl RateLimit {
+    pub fn check(&self, amount: Balance, daily_volume: Balance) -> Result<(), BridgeError> {
+        if matches!(self.max_transfer, Some(max) if amount > max.0) {
+            return Err(BridgeError::TransferLimitExceeded);
+        }
+        if matches!(self.max_daily_volume, Some(max) if daily_volume.saturating_add(amount) > max.0)
+        {
+            return Err(BridgeError::DailyVolumeLimitExceeded);
+        }
+        Ok(())
+    }
+}
+
+/// Rate limits of both directions, set by the DAO.
+#[derive(
+    Debug, Default, Clone, Eq, PartialEq, BorshSerialize, BorshDeserialize, Serialize, Deserialize,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RateLimits {
+    pub to_ethereum: RateLimit,
+    pub to_near: RateLimit,
+    /// Whether the Eth→NEAR transfers exceeding the limits are queued for the DAO approval
+    /// instead of being rejected. Migrations to Ethereum exceeding the limits are always
+    /// rejected, which returns the attached deposit.
+    pub queue_exceeding_transfers: bool,
+}
+
+impl RateLimits {
+    pub fn get(&self, direction: Direction) -> &RateLimit {
+        match direction {
+            Direction::ToEthereum => &self.to_ethereum,
+            Direction::ToNear => &self.to_near,
+        }
+    }
+}
+
+/// Volume transferred within the rolling 24 hours, tracked in hourly buckets.
+#[derive(Debug, Default, BorshSerialize, BorshDeserialize)]
+pub struct VolumeWindow {
+    /// Pairs of the bucket index and the volume transferred in it, oldest first.
+    buckets: Vec<(u64, Balance)>,
+}
+
+impl VolumeWindow {
+    /// Returns the volume of the last 24 buckets at `timestamp` in nanoseconds.
+    pub fn volume(&self, timestamp: u64) -> Balance {
+        let first_bucket = Self::first_bucket(timestamp);
+        self.buckets
+            .iter()
+            .filter(|(bucket, _)| *bucket >= first_bucket)
+            .fold(0, |volume, (_, amount)| volume.saturating_add(*amount))
+    }
+
+    pub fn add(&mut self, timestamp: u64, amount: Balance) {
+        let first_bucket = Self::first_bucket(timestamp);
+        self.buckets.retain(|(bucket, _)| *bucket >= first_bucket);
+
+        let current_bucket = timestamp / BUCKET_DURATION;
+        match self.buckets.last_mut() {
+            Some((bucket, volume)) if *bucket == current_bucket => {
+                *volume = volume.saturating_add(amount)
+            }
+            _ => self.buckets.push((current_bucket, amount)),
+        }
+    }
+
+    fn first_bucket(timestamp: u64) -> u64 {
+        (timestamp / BUCKET_DURATION).saturating_sub(WINDOW_BUCKETS - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_rate_limit() {
+        let limit = RateLimit {
+            max_transfer: Some(U128(100)),
+            max_daily_volume: Some(U128(250)),
+        };
+
+        assert_eq!(limit.check(100, 150), Ok(()));
+        assert_eq!(limit.check(101, 0), Err(BridgeError::TransferLimitExceeded));
+        assert_eq!(
+            limit.check(100, 151),
+            Err(BridgeError::DailyVolumeLimitExceeded)
+        );
+        assert_eq!(
+            RateLimit::default().check(Balance::MAX, Balance::MAX),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_volume_window() {
+        let mut window = VolumeWindow::default();
+        let hour = BUCKET_DURATION;
+
+        window.add(0, 10);
+        window.add(hour / 2, 20);
+        window.add(hour, 30);
+        assert_eq!(window.volume(hour), 60);
+        assert_eq!(window.volume(23 * hour), 60);
+        assert_eq!(window.volume(24 * hour), 30);
+        assert_eq!(window.volume(25 * hour), 0);
+
+        window.add(24 * hour, 40);
+        assert_eq!(window.buckets, vec![(1, 30), (24, 40)]);
+        assert_eq!(window.volume(24 * hour), 70);
+    }
+}
@@ -1,5 +1,6 @@
 use crate::prover::{EthAddress, EthEvent, EthEventParams};
-use ethabi::{ParamType, Token};
+use crate::BridgeError;
+use ethabi::{ParamType, Token, Uint};
 use hex::ToHex;
 use near_sdk::Balance;
 
@@ -22,27 +23,46 @@ impl TransferToNearInitiatedEvent {
     }
 
     /// Parse raw log entry data.
-    pub fn from_log_entry_data(data: &[u8]) -> Self {
+    pub fn from_log_entry_data(data: &[u8]) -> Result<Self, BridgeError> {
         let event = EthEvent::from_log_entry_data(
             "TransferToNearInitiated",
             TransferToNearInitiatedEvent::event_params(),
             data,
-        );
-        let sender = event.log.params[0].value.clone().to_address().unwrap().0;
-        let sender = (&sender).encode_hex::<String>();
-        let amount = event.log.params[1]
-            .value
-            .clone()
-            .to_uint()
-            .unwrap()
-            .as_u128();
-        let recipient = event.log.params[2].value.clone().to_string().unwrap();
-        Self {
+        )?;
+        let param = |index: usize| {
+            event
+                .log
+                .params
+                .get(index)
+                .map(|param| param.value.clone())
+                .ok_or(BridgeError::InvalidEventData)
+        };
+
+        let sender = param(0)?
+            .to_address()
+            .ok_or(BridgeError::WrongParamType)?
+            .0
+            .encode_hex::<String>();
+        let amount = Self::parse_amount(param(1)?.to_uint().ok_or(BridgeError::WrongParamType)?)?;
+        let recipient = param(2)?.to_string().ok_or(BridgeError::WrongParamType)?;
+        Ok(Self {
             e_near_address: event.locker_address,
             sender,
             amount,
             recipient,
+        })
+    }
+
+    /// Converts the uint256 amount of the event into `Balance`, rejecting zero and values which
+    /// don't fit into u128.
+    fn parse_amount(amount: Uint) -> Result<Balance, BridgeError> {
+        if amount.bits() > 128 {
+            return Err(BridgeError::AmountOverflow);
         }
+        if amount.is_zero() {
+            return Err(BridgeError::ZeroAmount);
+        }
+        Ok(amount.low_u128())
     }
 
     pub fn to_log_entry_data(&self) -> Vec<u8> {
@@ -76,13 +96,79 @@ mod tests {
     #[test]
     fn test_event_data() {
         let event_data = TransferToNearInitiatedEvent {
-            e_near_address: [0u8; 20],
+            e_near_address: EthAddress([0u8; 20]),
             sender: "00005474e89094c44da98b954eedeac495271d0f".to_string(),
             amount: 1000,
             recipient: "123".to_string(),
         };
         let data = event_data.to_log_entry_data();
-        let result = TransferToNearInitiatedEvent::from_log_entry_data(&data);
+        let result = TransferToNearInitiatedEvent::from_log_entry_data(&data).unwrap();
         assert_eq!(result, event_data);
     }
+
+    fn event_with_amount(amount: Uint) -> Vec<u8> {
+        EthEvent::to_log_entry_data(
+            "TransferToNearInitiated",
+            TransferToNearInitiatedEvent::event_params(),
+            EthAddress([0u8; 20]),
+            vec![vec![0u8; 20]],
+            vec![Token::Uint(amount), Token::String("alice.near".to_owned())],
+        )
+    }
+
+    #[test]
+    fn test_amount_bounds() {
+        let parse_amount = |amount: Uint| {
+            TransferToNearInitiatedEvent::from_log_entry_data(&event_with_amount(amount))
+                .map(|event| event.amount)
+        };
+
+        assert_eq!(parse_amount(Uint::from(1)), Ok(1));
+        assert_eq!(parse_amount(Uint::from(u128::MAX)), Ok(u128::MAX));
+        assert_eq!(parse_amount(Uint::zero()), Err(BridgeError::ZeroAmount));
+        assert_eq!(
+            parse_amount(Uint::from(u128::MAX) + 1),
+            Err(BridgeError::AmountOverflow)
+        );
+        assert_eq!(
+            parse_amount(Uint::from(1) << 200),
+            Err(BridgeError::AmountOverflow)
+        );
+        assert_eq!(parse_amount(Uint::MAX), Err(BridgeError::AmountOverflow));
+    }
+
+    #[test]
+    fn test_invalid_event_data() {
+        assert_eq!(
+            TransferToNearInitiatedEvent::from_log_entry_data(&[0x01, 0x02]),
+            Err(BridgeError::InvalidRlp)
+        );
+
+        let data = EthEvent::to_log_entry_data(
+            "TransferToNearInitiatedV2",
+            TransferToNearInitiatedEvent::event_params(),
+            EthAddress([0u8; 20]),
+            vec![vec![0u8; 20]],
+            vec![
+                Token::Uint(1000.into()),
+                Token::String("alice.near".to_owned()),
+            ],
+        );
+        assert_eq!(
+            TransferToNearInitiatedEvent::from_log_entry_data(&data),
+            Err(BridgeError::WrongTopic)
+        );
+
+        let data = EthEvent::to_log_entry_data(
+            "TransferToNearInitiated",
+            TransferToNearInitiatedEvent::event_params(),
+            EthAddress([0u8; 20]),
+            vec![vec![0u8; 20]],
+            vec![Token::Uint(1000.into())],
+        );
+        assert_eq!(
+            TransferToNearInitiatedEvent::from_log_entry_data(&data),
+            Err(BridgeError::InvalidEventData)
+        );
+    }
 }